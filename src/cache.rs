@@ -1,9 +1,449 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{self, Display};
+use std::fs::{self, File, OpenOptions};
 use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
 
-use ahash::AHasher;
+use cached::{Cached, UnboundCache};
+use once_cell::sync::Lazy;
 
-pub fn hash(value: impl Hash) -> u64 {
-    let mut hasher = AHasher::default();
+const FNV_OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// Hasher whose output only depends on the hashed value and the seed, so that hashes can be
+/// written to disk or to test snapshots
+///
+/// The algorithm is 128-bit FNV-1a, with the seed xored into the low half of the offset basis.
+/// Integers are fed in little-endian, `usize` and `isize` widened to 64 bits, so that the bytes
+/// are the same on every machine. The 64-bit hash xors both halves of the 128-bit one.
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    state: u128,
+    // every byte written, kept to check for collisions
+    bytes: Option<Vec<u8>>,
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::with_seed(0)
+    }
+}
+
+impl StableHasher {
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS ^ seed as u128,
+            bytes: None,
+        }
+    }
+
+    pub fn finish128(&self) -> u128 {
+        self.state
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u128;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+        if let Some(recorded) = &mut self.bytes {
+            recorded.extend_from_slice(bytes);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64)
+    }
+
+    fn finish(&self) -> u64 {
+        (self.state >> 64) as u64 ^ self.state as u64
+    }
+}
+
+type SeenHashes = Mutex<HashMap<(u64, u32, u128), Vec<u8>>>;
+
+/// In debug builds with the `AOC_CHECK_HASHES` environment variable set, the bytes behind every
+/// hash, by seed and width, to panic when two different values get the same one
+static SEEN_HASHES: Lazy<Option<SeenHashes>> = Lazy::new(|| {
+    (cfg!(debug_assertions) && env::var_os("AOC_CHECK_HASHES").is_some())
+        .then(|| Mutex::new(HashMap::new()))
+});
+
+/// Hash `value` with `seed`, then check the result given by `finish` for collisions
+fn checked_hash<H: Copy + Into<u128>>(
+    value: impl Hash,
+    seed: u64,
+    finish: impl Fn(&StableHasher) -> H,
+) -> H {
+    let mut hasher = StableHasher::with_seed(seed);
+    if SEEN_HASHES.is_some() {
+        hasher.bytes = Some(Vec::new());
+    }
     value.hash(&mut hasher);
-    hasher.finish()
+    let hash = finish(&hasher);
+    if let (Some(seen), Some(bytes)) = (SEEN_HASHES.as_ref(), hasher.bytes) {
+        let bits = 8 * mem::size_of::<H>() as u32;
+        let mut seen = seen.lock().unwrap();
+        let previous = seen
+            .entry((seed, bits, hash.into()))
+            .or_insert_with(|| bytes.clone());
+        assert!(
+            previous == &bytes,
+            "hash collision on {:0width$x}: {previous:?} and {bytes:?}",
+            hash.into(),
+            width = bits as usize / 4
+        );
+    }
+    hash
+}
+
+/// Stable 64-bit hash, see [`StableHasher`]
+pub fn hash(value: impl Hash) -> u64 {
+    hash_with_seed(value, 0)
+}
+
+pub fn hash_with_seed(value: impl Hash, seed: u64) -> u64 {
+    checked_hash(value, seed, StableHasher::finish)
+}
+
+/// Stable 128-bit hash, for when collisions of 64-bit hashes are a concern
+#[allow(dead_code)]
+pub fn hash128(value: impl Hash) -> u128 {
+    hash128_with_seed(value, 0)
+}
+
+pub fn hash128_with_seed(value: impl Hash, seed: u64) -> u128 {
+    checked_hash(value, seed, StableHasher::finish128)
+}
+
+/// Effectiveness of the memo table of a function
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheStats {
+    pub function: &'static str,
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    /// Approximate size of the keys and values, in bytes
+    pub memory: usize,
+}
+
+impl CacheStats {
+    /// Hits and misses that happened since `before` was taken
+    pub fn since(&self, before: &Self) -> Self {
+        Self {
+            hits: self.hits - before.hits,
+            misses: self.misses - before.misses,
+            ..self.clone()
+        }
+    }
+}
+
+impl Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lookups = self.hits + self.misses;
+        let hit_rate = if lookups > 0 {
+            100. * self.hits as f64 / lookups as f64
+        } else {
+            0.
+        };
+        write!(
+            f,
+            "{}: {} hits, {} misses ({hit_rate:.1}% hit rate), {} entries, ~{} KiB",
+            self.function,
+            self.hits,
+            self.misses,
+            self.entries,
+            self.memory.div_ceil(1024),
+        )
+    }
+}
+
+/// Approximate memory used by a value, including what it owns on the heap
+pub trait MemorySize {
+    fn memory_size(&self) -> usize;
+}
+
+macro_rules! impl_memory_size {
+    ($($type:ty),*) => {
+        $(impl MemorySize for $type {
+            fn memory_size(&self) -> usize {
+                mem::size_of::<Self>()
+            }
+        })*
+    };
+}
+
+impl_memory_size!(u8, u32, u64, usize, char);
+
+impl<T: MemorySize> MemorySize for Vec<T> {
+    fn memory_size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.iter().map(MemorySize::memory_size).sum::<usize>()
+            + (self.capacity() - self.len()) * mem::size_of::<T>()
+    }
+}
+
+/// Statistics of a table generated by `#[cached(key = "u64", ...)]`
+pub fn stats<V: MemorySize>(
+    function: &'static str,
+    cache: &Mutex<UnboundCache<u64, V>>,
+) -> CacheStats {
+    let cache = cache.lock().unwrap();
+    CacheStats {
+        function,
+        hits: cache.cache_hits().unwrap_or(0),
+        misses: cache.cache_misses().unwrap_or(0),
+        entries: cache.cache_size(),
+        memory: cache
+            .get_store()
+            .values()
+            .map(|value| mem::size_of::<u64>() + value.memory_size())
+            .sum(),
+    }
+}
+
+/// On-disk memo table of a single function, one `key value` line per entry
+///
+/// Values must be displayed on a single line and parsed back by their [`FromStr`] implementation
+pub struct PersistentStore<V> {
+    entries: HashMap<u64, V>,
+    file: File,
+}
+
+impl<V: Display + FromStr> PersistentStore<V> {
+    /// Open the store of `function` in the directory set by the `AOC_CACHE_DIR` environment
+    /// variable, or return `None` if it isn't set
+    ///
+    /// `function` names the file of the store, so it may only contain ASCII letters, digits, `_`
+    /// and `-`. `version` must be bumped whenever the function changes, the stores of its other
+    /// versions are then deleted
+    pub fn open(function: &str, version: u32) -> io::Result<Option<Self>> {
+        match env::var_os("AOC_CACHE_DIR") {
+            Some(directory) => Self::open_in(directory, function, version).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn open_in(directory: impl AsRef<Path>, function: &str, version: u32) -> io::Result<Self> {
+        if function.is_empty()
+            || !function
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{function:?} can't be used as a file name"),
+            ));
+        }
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        let file_name = format!("{function}.v{version}");
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let is_other_version = name
+                .to_string_lossy()
+                .strip_prefix(&format!("{function}.v"))
+                .is_some_and(|other| {
+                    !other.is_empty()
+                        && other.bytes().all(|byte| byte.is_ascii_digit())
+                        && other != version.to_string()
+                });
+            if is_other_version {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        let path = directory.join(file_name);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+        // lines cut short by an interrupted run fail to parse and are skipped
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(' ')?;
+                Some((u64::from_str_radix(key, 16).ok()?, value.parse().ok()?))
+            })
+            .collect();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if !content.is_empty() && !content.ends_with('\n') {
+            file.write_all(b"\n")?;
+        }
+        Ok(Self { entries, file })
+    }
+
+    pub fn get(&self, key: u64) -> Option<&V> {
+        self.entries.get(&key)
+    }
+
+    pub fn insert(&mut self, key: u64, value: V) -> io::Result<()> {
+        writeln!(self.file, "{key:016x} {value}")?;
+        self.entries.insert(key, value);
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<V: Display + FromStr + Clone> PersistentStore<V> {
+    /// Return the stored value, or compute and store it
+    pub fn get_or_insert_with(&mut self, key: u64, function: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(key) {
+            return value.clone();
+        }
+        let value = function();
+        self.insert(key, value.clone()).unwrap();
+        value
+    }
+}
+
+/// Memoize through `store` if there is one, otherwise simply call `function`
+pub fn persisted<V: Display + FromStr + Clone>(
+    store: &mut Option<PersistentStore<V>>,
+    key: u64,
+    function: impl FnOnce() -> V,
+) -> V {
+    match store {
+        Some(store) => store.get_or_insert_with(key, function),
+        None => function(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stable_hash() {
+        // FNV-1a test vectors
+        let mut hasher = StableHasher::default();
+        assert_eq!(hasher.finish128(), 0x6c62272e07bb014262b821756295c58d);
+        hasher.write(b"a");
+        assert_eq!(hasher.finish128(), 0xd228cb696f1a8caf78912b704e4a8964);
+
+        // integers are hashed the same whatever their width on the machine
+        assert_eq!(hash(12usize), hash(12u64));
+        assert_eq!(hash(-1isize), hash(u64::MAX));
+        assert_ne!(hash_with_seed(12, 1), hash(12));
+        // snapshots, these must never change as they key the persistent stores
+        assert_eq!(hash(("#.#", [1, 1])), 0x567c39c22c5c3349);
+        assert_eq!(hash128("rx"), 0xa68db94de38b5822836dbc799b97a1d0);
+        assert_ne!(hash128_with_seed("rx", 1), hash128("rx"));
+        assert_eq!(
+            hash_with_seed("rx", 1),
+            hash128_with_seed("rx", 1) as u64 ^ (hash128_with_seed("rx", 1) >> 64) as u64
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let cache = Mutex::new(UnboundCache::new());
+        let before = stats("square", &cache);
+        {
+            let mut cache = cache.lock().unwrap();
+            cache.cache_set(hash(12), vec![1u64, 4]);
+            assert!(cache.cache_get(&hash(12)).is_some());
+            assert!(cache.cache_get(&hash(3)).is_none());
+        }
+        let after = stats("square", &cache).since(&before);
+        assert_eq!((after.hits, after.misses, after.entries), (1, 1, 1));
+        assert_eq!(after.memory, 8 + mem::size_of::<Vec<u64>>() + 2 * 8);
+        assert_eq!(
+            after.to_string(),
+            "square: 1 hits, 1 misses (50.0% hit rate), 1 entries, ~1 KiB"
+        );
+    }
+
+    #[test]
+    fn test_persistent_store() {
+        let directory = env::temp_dir().join(format!("aoc-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut store = PersistentStore::<usize>::open_in(&directory, "square", 1).unwrap();
+        assert!(store.is_empty());
+        assert_eq!(store.get_or_insert_with(hash(12), || 144), 144);
+        drop(store);
+
+        let mut store = PersistentStore::<usize>::open_in(&directory, "square", 1).unwrap();
+        assert_eq!(store.get(hash(12)), Some(&144));
+        // already stored, so not recomputed
+        assert_eq!(store.get_or_insert_with(hash(12), || unreachable!()), 144);
+        drop(store);
+
+        // an interrupted write leaves a partial line behind
+        let path = directory.join("square.v1");
+        fs::write(&path, fs::read_to_string(&path).unwrap() + "0123").unwrap();
+        let mut store = PersistentStore::<usize>::open_in(&directory, "square", 1).unwrap();
+        assert_eq!(store.len(), 1);
+        store.insert(hash(3), 9).unwrap();
+        drop(store);
+        let store = PersistentStore::<usize>::open_in(&directory, "square", 1).unwrap();
+        assert_eq!(store.get(hash(3)), Some(&9));
+        drop(store);
+
+        // a new version drops the old entries, but not files that merely start like them
+        let other = directory.join("square.v1x");
+        fs::write(&other, "").unwrap();
+        let store = PersistentStore::<usize>::open_in(&directory, "square", 2).unwrap();
+        assert!(store.is_empty());
+        assert!(!path.exists());
+        assert!(other.exists());
+
+        assert!(PersistentStore::<usize>::open_in(&directory, "day12::count", 1).is_err());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
 }
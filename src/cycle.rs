@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::cache::hash;
+
+/// The states of a sequence repeat every `length` steps once `start` steps are done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// The earliest step whose state is the same as after `n` steps
+    pub fn reduce(&self, n: usize) -> usize {
+        if n < self.start {
+            n
+        } else {
+            self.start + (n - self.start) % self.length
+        }
+    }
+}
+
+/// Brent's algorithm, only keeps two states at a time but calls `step` up to three times as
+/// often as [`find`]
+#[allow(dead_code)]
+pub fn brent<S: Clone + PartialEq>(initial: &S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // find the length by moving the tortoise to the hare at every power of two
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+    // then walk both from the start, `length` steps apart, until they meet
+    let mut start = 0;
+    tortoise = initial.clone();
+    hare = initial.clone();
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, length }
+}
+
+/// Walk from `initial` until a state repeats or `limit` steps are done, keeping every state
+///
+/// States are looked up by their hash, and compared to rule out collisions
+fn explore<S: Hash + Eq>(
+    initial: S,
+    mut step: impl FnMut(&S) -> S,
+    limit: usize,
+) -> (Vec<S>, Option<Cycle>) {
+    let mut seen = HashMap::<u64, Vec<usize>>::new();
+    seen.entry(hash(&initial)).or_default().push(0);
+    let mut states = vec![initial];
+    while states.len() <= limit {
+        let next = step(states.last().unwrap());
+        let key = hash(&next);
+        let entry = seen.entry(key).or_default();
+        if let Some(&start) = entry.iter().find(|index| states[**index] == next) {
+            let length = states.len() - start;
+            return (states, Some(Cycle { start, length }));
+        }
+        entry.push(states.len());
+        states.push(next);
+    }
+    (states, None)
+}
+
+/// Find the cycle with a history of the hashed states, calls `step` once per state
+#[allow(dead_code)]
+pub fn find<S: Hash + Eq>(initial: S, step: impl FnMut(&S) -> S) -> Cycle {
+    explore(initial, step, usize::MAX).1.unwrap()
+}
+
+/// The state after `n` steps, skipping the repetitions of the cycle
+pub fn nth<S: Hash + Eq>(initial: S, n: usize, step: impl FnMut(&S) -> S) -> S {
+    let (mut states, cycle) = explore(initial, step, n);
+    match cycle {
+        Some(cycle) => states.swap_remove(cycle.reduce(n)),
+        None => states.pop().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycles() {
+        // the powers of 3 modulo 100 cycle back to 3 after 20 steps
+        let step = |x: &u32| x * 3 % 100;
+        let expected = Cycle {
+            start: 0,
+            length: 20,
+        };
+        assert_eq!(brent(&3, step), expected);
+        assert_eq!(find(3, step), expected);
+
+        // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 6 -> 7 -> 8 -> 9 -> 4
+        let step = |x: &u32| if *x == 9 { 4 } else { x + 1 };
+        let expected = Cycle {
+            start: 4,
+            length: 6,
+        };
+        assert_eq!(brent(&0, step), expected);
+        assert_eq!(find(0, step), expected);
+        assert_eq!(expected.reduce(3), 3);
+        assert_eq!(expected.reduce(81), 9);
+
+        assert_eq!(nth(0, 3, step), 3);
+        assert_eq!(nth(0, 1_000_000_000, step), 4);
+        assert_eq!(nth(0, 1_000_000_003, step), 7);
+    }
+}
@@ -1,49 +1,239 @@
-use crate::regex;
+use std::fmt::Write;
+use std::str::FromStr;
 
-pub fn part_one(input: &str) -> u32 {
-    regex!(r"(?m)(?P<first>\d)(?:.*(?P<last>\d))?")
-        .captures_iter(input)
-        .map(|caps| {
-            let first = caps["first"].parse::<u32>().unwrap();
-            10 * first
-                + caps
-                    .name("last")
-                    .map(|last| last.as_str().parse().unwrap())
-                    .unwrap_or(first)
-        })
+/// Tokens that stand for a digit, on top of the digits themselves
+pub type Vocabulary<'a> = &'a [(&'a str, u32)];
+
+pub const ENGLISH: Vocabulary = &[
+    ("one", 1),
+    ("two", 2),
+    ("three", 3),
+    ("four", 4),
+    ("five", 5),
+    ("six", 6),
+    ("seven", 7),
+    ("eight", 8),
+    ("nine", 9),
+];
+
+/// A digit or a token of the vocabulary, found in a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    /// In bytes, from the start of the line
+    pub position: usize,
+    pub digit: u32,
+}
+
+/// How a line decodes, `first` and `last` are `None` if it has no digit at all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration<'a> {
+    /// 1-based
+    pub line_number: usize,
+    pub line: &'a str,
+    pub first: Option<Token<'a>>,
+    pub last: Option<Token<'a>>,
+}
+
+impl Calibration<'_> {
+    pub fn value(&self) -> Option<u32> {
+        Some(10 * self.first.as_ref()?.digit + self.last.as_ref()?.digit)
+    }
+}
+
+/// The longest of the matching tokens and the digit character, which wins ties
+///
+/// `candidates` are the tokens that match, `digit_character` the character next to them
+fn longest_token<'a>(
+    candidates: impl Iterator<Item = (&'a str, u32)>,
+    digit_character: Option<(&'a str, char)>,
+) -> Option<(&'a str, u32)> {
+    candidates
+        .chain(digit_character.and_then(|(text, char)| Some((text, char.to_digit(10)?))))
+        .max_by_key(|(token, _)| token.len())
+}
+
+fn token_starting_at<'a>(line: &'a str, index: usize, vocabulary: Vocabulary) -> Option<Token<'a>> {
+    let rest = &line[index..];
+    let (text, digit) = longest_token(
+        vocabulary
+            .iter()
+            .filter(|(token, _)| rest.starts_with(token))
+            .map(|(token, digit)| (&rest[..token.len()], *digit)),
+        rest.chars()
+            .next()
+            .map(|char| (&rest[..char.len_utf8()], char)),
+    )?;
+    Some(Token {
+        text,
+        position: index,
+        digit,
+    })
+}
+
+fn token_ending_at<'a>(line: &'a str, index: usize, vocabulary: Vocabulary) -> Option<Token<'a>> {
+    let start = &line[..index];
+    let (text, digit) = longest_token(
+        vocabulary
+            .iter()
+            .filter(|(token, _)| start.ends_with(token))
+            .map(|(token, digit)| (&start[(index - token.len())..], *digit)),
+        start
+            .chars()
+            .next_back()
+            .map(|char| (&start[(index - char.len_utf8())..], char)),
+    )?;
+    Some(Token {
+        text,
+        position: index - text.len(),
+        digit,
+    })
+}
+
+/// Find the first and the last digits of a line, scanning from each end so that overlapping
+/// tokens such as "eightwo" count as both 8 and 2
+fn calibration<'a>(line_number: usize, line: &'a str, vocabulary: Vocabulary) -> Calibration<'a> {
+    let first = (0..line.len())
+        .filter(|index| line.is_char_boundary(*index))
+        .find_map(|index| token_starting_at(line, index, vocabulary));
+    let last = (1..=line.len())
+        .rev()
+        .filter(|index| line.is_char_boundary(*index))
+        .find_map(|index| token_ending_at(line, index, vocabulary));
+    Calibration {
+        line_number,
+        line,
+        first,
+        last,
+    }
+}
+
+/// How every line of the input decodes, including the lines without any digit
+///
+/// Panics if a token of `vocabulary` is empty, as it would match everywhere.
+pub fn calibrations<'a>(
+    input: &'a str,
+    vocabulary: Vocabulary<'a>,
+) -> impl Iterator<Item = Calibration<'a>> {
+    if let Some((_, digit)) = vocabulary.iter().find(|(token, _)| token.is_empty()) {
+        panic!("empty token for {digit} in the vocabulary");
+    }
+    input
+        .lines()
+        .enumerate()
+        .map(move |(index, line)| calibration(index + 1, line, vocabulary))
+}
+
+fn calibration_sum(input: &str, vocabulary: Vocabulary) -> u32 {
+    calibrations(input, vocabulary)
+        .filter_map(|calibration| calibration.value())
         .sum()
 }
 
+pub fn part_one(input: &str) -> u32 {
+    calibration_sum(input, vocabulary(1).unwrap())
+}
+
 pub fn part_two(input: &str) -> u32 {
-    fn parse_digit(input: &str) -> u32 {
-        match input {
-            "0" => 0,
-            "1" | "one" => 1,
-            "2" | "two" => 2,
-            "3" | "three" => 3,
-            "4" | "four" => 4,
-            "5" | "five" => 5,
-            "6" | "six" => 6,
-            "7" | "seven" => 7,
-            "8" | "eight" => 8,
-            "9" | "nine" => 9,
-            _ => unreachable!(),
+    calibration_sum(input, vocabulary(2).unwrap())
+}
+
+pub enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            _ => Err(()),
         }
     }
+}
 
-    regex!(
-        r"(?m)(?P<first>\d|one|two|three|four|five|six|seven|eight|nine)(?:.*(?P<last>\d|one|two|three|four|five|six|seven|eight|nine))?"
-    )
-    .captures_iter(input)
-    .map(|caps| {
-        let first = parse_digit(&caps["first"]);
-        10 * first
-            + caps
-                .name("last")
-                .map(|last| parse_digit(last.as_str()))
-                .unwrap_or(first)
-    })
-    .sum()
+fn json_string(text: &str) -> String {
+    let mut json = String::from('"');
+    for char in text.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            char if char.is_control() => write!(json, "\\u{:04x}", char as u32).unwrap(),
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn json_token(token: &Option<Token>) -> String {
+    match token {
+        Some(token) => format!(
+            r#"{{"text": {}, "position": {}, "digit": {}}}"#,
+            json_string(token.text),
+            token.position,
+            token.digit
+        ),
+        None => "null".to_owned(),
+    }
+}
+
+/// The vocabulary of a part, so that the decoding of either can be reported
+pub fn vocabulary(part: u32) -> Option<Vocabulary<'static>> {
+    match part {
+        1 => Some(&[]),
+        2 => Some(ENGLISH),
+        _ => None,
+    }
+}
+
+/// How every line decodes with `vocabulary`, lines without digits are flagged
+pub fn report(input: &str, vocabulary: Vocabulary, format: ReportFormat) -> String {
+    let mut report = String::new();
+    match format {
+        ReportFormat::Table => {
+            writeln!(report, "line  first       last        value").unwrap();
+            for calibration in calibrations(input, vocabulary) {
+                let cell = |token: &Option<Token>| match token {
+                    Some(token) => format!("{}@{}", token.text, token.position),
+                    None => "-".to_owned(),
+                };
+                let value = match calibration.value() {
+                    Some(value) => value.to_string(),
+                    None => format!("!! no digits in {:?}", calibration.line),
+                };
+                writeln!(
+                    report,
+                    "{:<5} {:<11} {:<11} {value}",
+                    calibration.line_number,
+                    cell(&calibration.first),
+                    cell(&calibration.last)
+                )
+                .unwrap();
+            }
+        }
+        ReportFormat::Json => {
+            let lines = calibrations(input, vocabulary)
+                .map(|calibration| {
+                    format!(
+                        r#"  {{"line": {}, "text": {}, "first": {}, "last": {}, "value": {}}}"#,
+                        calibration.line_number,
+                        json_string(calibration.line),
+                        json_token(&calibration.first),
+                        json_token(&calibration.last),
+                        calibration
+                            .value()
+                            .map_or("null".to_owned(), |value| value.to_string())
+                    )
+                })
+                .collect::<Vec<_>>();
+            writeln!(report, "[\n{}\n]", lines.join(",\n")).unwrap();
+        }
+    }
+    report
 }
 
 #[cfg(test)]
@@ -52,6 +242,10 @@ mod tests {
 
     use super::*;
 
+    fn calibration_value(line: &str, vocabulary: Vocabulary) -> Option<u32> {
+        calibration(1, line, vocabulary).value()
+    }
+
     #[test]
     fn test_part_one() {
         let input = read_to_string("examples/01/1").unwrap();
@@ -63,4 +257,85 @@ mod tests {
         let input = read_to_string("examples/01/2").unwrap();
         assert_eq!(part_two(&input), 281);
     }
+
+    #[test]
+    fn test_vocabularies() {
+        assert_eq!(calibration_value("eightwo", ENGLISH), Some(82));
+        assert_eq!(calibration_value("oneight", ENGLISH), Some(18));
+        assert_eq!(calibration_value("seven", ENGLISH), Some(77));
+        assert_eq!(calibration_value("abc", ENGLISH), None);
+
+        let french: Vocabulary = &[
+            ("zéro", 0),
+            ("un", 1),
+            ("deux", 2),
+            ("trois", 3),
+            ("sept", 7),
+            ("huit", 8),
+            ("neuf", 9),
+        ];
+        assert_eq!(calibration_value("zérotroisept", french), Some(7));
+        assert_eq!(calibration_value("àdeuxhuitrois", french), Some(23));
+
+        // the longest token wins when several start at the same place
+        let nested: Vocabulary = &[("one", 1), ("oneself", 9)];
+        assert_eq!(calibration_value("oneself", nested), Some(99));
+
+        let with_zero: Vocabulary = &[("zero", 0), ("one", 1)];
+        assert_eq!(calibration_value("onezero", with_zero), Some(10));
+
+        // tokens may start with a digit
+        let ordinals: Vocabulary = &[("1st", 1), ("2nd", 2), ("10th", 0)];
+        assert_eq!(calibration_value("1st of the 2nd", ordinals), Some(12));
+        assert_eq!(calibration_value("10th", ordinals), Some(0));
+        assert_eq!(calibration_value("3rd", ordinals), Some(33));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty token for 5 in the vocabulary")]
+    fn test_empty_token() {
+        calibrations("abc\n", &[("one", 1), ("", 5)]).count();
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "xtwone3four\nabc\n";
+        assert_eq!(
+            calibrations(input, ENGLISH).next().unwrap(),
+            Calibration {
+                line_number: 1,
+                line: "xtwone3four",
+                first: Some(Token {
+                    text: "two",
+                    position: 1,
+                    digit: 2
+                }),
+                last: Some(Token {
+                    text: "four",
+                    position: 7,
+                    digit: 4
+                }),
+            }
+        );
+        assert_eq!(
+            report(input, ENGLISH, ReportFormat::Table),
+            "line  first       last        value\n\
+             1     two@1       four@7      24\n\
+             2     -           -           !! no digits in \"abc\"\n"
+        );
+        assert_eq!(
+            report(input, ENGLISH, ReportFormat::Json),
+            r#"[
+  {"line": 1, "text": "xtwone3four", "first": {"text": "two", "position": 1, "digit": 2}, "last": {"text": "four", "position": 7, "digit": 4}, "value": 24},
+  {"line": 2, "text": "abc", "first": null, "last": null, "value": null}
+]
+"#
+        );
+        assert_eq!(
+            report(input, vocabulary(1).unwrap(), ReportFormat::Table),
+            "line  first       last        value\n\
+             1     3@6         3@6         33\n\
+             2     -           -           !! no digits in \"abc\"\n"
+        );
+    }
 }
@@ -1,61 +1,142 @@
-use crate::regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-pub fn part_one(input: &str) -> u32 {
-    regex!(r"(?m)Game (?P<game>\d+): (?P<cubes>.+)")
-        .captures_iter(input)
-        .filter_map(|caps| {
-            if regex!(r"(?P<set>\d[^;]+)")
-                .captures_iter(&caps["cubes"])
-                .all(|caps| {
-                    regex!(r"(?P<count>\d+) (?P<color>red|green|blue)")
-                        .captures_iter(&caps["set"])
-                        .all(|caps| {
-                            caps["count"].parse::<u32>().unwrap()
-                                <= match &caps["color"] {
-                                    "red" => 12,
-                                    "green" => 13,
-                                    "blue" => 14,
-                                    _ => unreachable!(),
-                                }
-                        })
+use crate::macros::CaptureError;
+use crate::{parse_caps, regex};
+
+pub type Colour = String;
+
+/// How many cubes of each colour there are in a bag
+pub type Bag = HashMap<Colour, u32>;
+
+pub struct Game {
+    pub id: u32,
+    /// Counts in the order they were shown
+    pub sets: Vec<Vec<(Colour, u32)>>,
+}
+
+impl FromStr for Game {
+    type Err = CaptureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = regex!(r"^Game (?P<id>\d+): (?P<sets>.+)$")
+            .captures(s)
+            .ok_or_else(|| CaptureError {
+                group: "id",
+                text: Some(s.to_owned()),
+            })?;
+        let (id,) = parse_caps!(caps, ("id",))?;
+        let sets = caps["sets"]
+            .split("; ")
+            .map(|set| {
+                set.split(", ")
+                    .map(|cubes| {
+                        let caps = regex!(r"^(?P<count>\d+) (?P<colour>\w+)$")
+                            .captures(cubes)
+                            .ok_or_else(|| CaptureError {
+                                group: "sets",
+                                text: Some(cubes.to_owned()),
+                            })?;
+                        let (count, colour) = parse_caps!(caps, ("count", "colour"))?;
+                        Ok((colour, count))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { id, sets })
+    }
+}
+
+/// More cubes of a colour were shown than the bag holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// 0-based
+    pub set: usize,
+    pub colour: Colour,
+    pub shown: u32,
+    pub available: u32,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "set {} shows {} {} but the bag only has {}",
+            self.set + 1,
+            self.shown,
+            self.colour,
+            self.available
+        )
+    }
+}
+
+impl Game {
+    /// Every colour of every set that doesn't fit in `bag`, colours missing from it have no cubes
+    pub fn failures(&self, bag: &Bag) -> Vec<Failure> {
+        self.sets
+            .iter()
+            .enumerate()
+            .flat_map(|(index, set)| {
+                set.iter().filter_map(move |(colour, shown)| {
+                    let available = bag.get(colour).copied().unwrap_or(0);
+                    (*shown > available).then(|| Failure {
+                        set: index,
+                        colour: colour.clone(),
+                        shown: *shown,
+                        available,
+                    })
                 })
-            {
-                Some(caps["game"].parse::<u32>().unwrap())
-            } else {
-                None
-            }
-        })
+            })
+            .collect()
+    }
+
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.failures(bag).is_empty()
+    }
+
+    /// The smallest bag that makes the game possible, with only the colours that were shown
+    pub fn minimum_bag(&self) -> Bag {
+        let mut bag = Bag::new();
+        for (colour, count) in self.sets.iter().flatten() {
+            let minimum = bag.entry(colour.clone()).or_insert(0);
+            *minimum = (*minimum).max(*count);
+        }
+        bag
+    }
+}
+
+fn parse(input: &str) -> impl Iterator<Item = Game> + '_ {
+    input.lines().map(|line| line.parse().unwrap())
+}
+
+/// Product of the counts of `colours` in `bag`, which is 0 if one of them is missing
+fn power(bag: &Bag, colours: &[&str]) -> u32 {
+    colours
+        .iter()
+        .map(|colour| bag.get(*colour).copied().unwrap_or(0))
+        .product()
+}
+
+fn elf_bag() -> Bag {
+    Bag::from([
+        ("red".to_owned(), 12),
+        ("green".to_owned(), 13),
+        ("blue".to_owned(), 14),
+    ])
+}
+
+pub fn part_one(input: &str) -> u32 {
+    let bag = elf_bag();
+    parse(input)
+        .filter(|game| game.is_possible(&bag))
+        .map(|game| game.id)
         .sum()
 }
 
 pub fn part_two(input: &str) -> u32 {
-    regex!(r"(?m)Game (?P<game>\d+): (?P<cubes>.+)")
-        .captures_iter(input)
-        .map(|caps| {
-            let res = regex!(r"(?P<set>\d[^;]+)")
-                .captures_iter(&caps["cubes"])
-                .map(|caps| {
-                    regex!(r"(?P<count>\d+) (?P<color>red|green|blue)")
-                        .captures_iter(&caps["set"])
-                        .fold((0, 0, 0), |res, caps| {
-                            let count = caps["count"].parse::<u32>().unwrap();
-                            match &caps["color"] {
-                                "red" => (res.0.max(count), res.1, res.2),
-                                "green" => (res.0, res.1.max(count), res.2),
-                                "blue" => (res.0, res.1, res.2.max(count)),
-                                _ => unreachable!(),
-                            }
-                        })
-                })
-                .fold((0, 0, 0), |res, colors| {
-                    (
-                        res.0.max(colors.0),
-                        res.1.max(colors.1),
-                        res.2.max(colors.2),
-                    )
-                });
-            res.0 * res.1 * res.2
-        })
+    parse(input)
+        .map(|game| power(&game.minimum_bag(), &["red", "green", "blue"]))
         .sum()
 }
 
@@ -76,4 +157,72 @@ mod tests {
         let input = read_to_string("examples/02/1").unwrap();
         assert_eq!(part_two(&input), 2286);
     }
+
+    #[test]
+    fn test_bags() {
+        let input = read_to_string("examples/02/1").unwrap();
+        let games = parse(&input).collect::<Vec<_>>();
+
+        let failures = games[2].failures(&elf_bag());
+        assert_eq!(
+            failures,
+            vec![Failure {
+                set: 0,
+                colour: "red".to_owned(),
+                shown: 20,
+                available: 12
+            }]
+        );
+        assert_eq!(
+            failures[0].to_string(),
+            "set 1 shows 20 red but the bag only has 12"
+        );
+        assert_eq!(
+            games[0].minimum_bag(),
+            Bag::from([
+                ("red".to_owned(), 4),
+                ("green".to_owned(), 2),
+                ("blue".to_owned(), 6)
+            ])
+        );
+
+        // any colours, missing ones have no cubes
+        let game = "Game 7: 2 yellow, 1 red; 3 yellow".parse::<Game>().unwrap();
+        let bag = Bag::from([("yellow".to_owned(), 3)]);
+        assert_eq!(
+            game.failures(&bag)
+                .iter()
+                .map(|failure| (failure.colour.as_str(), failure.available))
+                .collect::<Vec<_>>(),
+            [("red", 0)]
+        );
+        assert!(game.is_possible(&game.minimum_bag()));
+
+        // blue is never shown, so the power is 0; failures come in the order they were shown
+        let game = "Game 1: 3 red, 2 green".parse::<Game>().unwrap();
+        assert_eq!(part_two("Game 1: 3 red, 2 green\n"), 0);
+        assert_eq!(
+            game.failures(&Bag::new())
+                .iter()
+                .map(|failure| failure.colour.as_str())
+                .collect::<Vec<_>>(),
+            ["red", "green"]
+        );
+
+        assert_eq!(
+            "Game 1: 3 red, 2green".parse::<Game>().err(),
+            Some(CaptureError {
+                group: "sets",
+                text: Some("2green".to_owned())
+            })
+        );
+        assert_eq!(
+            "Game 1: 3 red; 99999999999 blue".parse::<Game>().err(),
+            Some(CaptureError {
+                group: "count",
+                text: Some("99999999999".to_owned())
+            })
+        );
+        assert!("Game x: 3 red".parse::<Game>().is_err());
+    }
 }
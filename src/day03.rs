@@ -1,81 +1,131 @@
-use std::collections::{HashMap, HashSet};
+use crate::grid::{Cells, Coordinates, Grid};
 
-use crate::regex;
+/// Which symbols are gears, and how many numbers they must touch
+pub struct GearRule<'a> {
+    pub symbols: &'a [u8],
+    pub adjacent_numbers: usize,
+}
 
-pub fn part_one(input: &str) -> u32 {
-    // HashMap<line_index, HashSet<indices_on_line>>
-    let symbols = input
-        .lines()
-        .enumerate()
-        .flat_map(|(line_index, line)| {
-            regex!(r"(?P<symbol>[^\d.])")
-                .captures_iter(line)
-                .map(move |caps| (line_index, caps.name("symbol").unwrap().start()))
-        })
-        .collect::<HashSet<_>>();
-    input
-        .lines()
-        .enumerate()
-        .map(|(line_index, line)| {
-            regex!(r"(?P<number>\d+)")
-                .captures_iter(line)
-                .filter_map(|caps| {
-                    let cap = caps.name("number").unwrap();
-                    let index_range = cap.start().saturating_sub(1)..(cap.end() + 1);
-                    if symbols.iter().any(|(symbol_line_index, symbol_index)| {
-                        (line_index.saturating_sub(1)..(line_index + 2)).contains(symbol_line_index)
-                            && index_range.contains(symbol_index)
-                    }) {
-                        Some(cap.as_str().parse::<u32>().unwrap())
-                    } else {
-                        None
-                    }
-                })
-                .sum::<u32>()
-        })
-        .sum()
+const STAR_BETWEEN_TWO_NUMBERS: GearRule = GearRule {
+    symbols: b"*",
+    adjacent_numbers: 2,
+};
+
+pub struct Schematic {
+    grid: Grid<u8>,
+    numbers: Vec<u32>,
+    /// Index in `numbers` of the number each digit belongs to
+    number_at: Grid<Option<usize>>,
 }
 
-pub fn part_two(input: &str) -> u32 {
-    let mut stars = input
-        .lines()
-        .enumerate()
-        .flat_map(|(line_index, line)| {
-            regex!(r"(?P<star>\*)")
-                .captures_iter(line)
-                .map(move |caps| {
-                    (
-                        (line_index, caps.name("star").unwrap().start()),
-                        Vec::<u32>::with_capacity(2),
-                    )
-                })
-        })
-        .collect::<HashMap<_, _>>();
-    for (line_index, line) in input.lines().enumerate() {
-        for caps in regex!(r"(?P<number>\d+)").captures_iter(line) {
-            let cap = caps.name("number").unwrap();
-            let index_range = cap.start().saturating_sub(1)..(cap.end() + 1);
-            let number = cap.as_str().parse::<u32>().unwrap();
-            for (_, gears) in stars
-                .iter_mut()
-                .filter(|((star_line_index, star_index), _)| {
-                    (line_index.saturating_sub(1)..(line_index + 2)).contains(star_line_index)
-                        && index_range.contains(star_index)
-                })
-            {
-                gears.push(number);
+fn is_symbol(cell: &u8) -> bool {
+    !cell.is_ascii_digit() && cell != &b'.'
+}
+
+impl Schematic {
+    pub fn new(input: &str) -> Self {
+        let grid = Grid::from_iter(input.lines().map(|line| line.bytes()));
+        let mut numbers = Vec::new();
+        let mut number_at = grid.clone_with(None);
+        for (y, row) in grid.rows().enumerate() {
+            let mut x = 0;
+            while x < row.len() {
+                let length = row[x..]
+                    .iter()
+                    .take_while(|cell| cell.is_ascii_digit())
+                    .count();
+                if length == 0 {
+                    x += 1;
+                    continue;
+                }
+                for digit_x in x..(x + length) {
+                    number_at[&Coordinates { x: digit_x, y }] = Some(numbers.len());
+                }
+                numbers.push(
+                    row[x..(x + length)]
+                        .iter()
+                        .fold(0, |value, digit| 10 * value + (digit - b'0') as u32),
+                );
+                x += length;
+            }
+        }
+        Self {
+            grid,
+            numbers,
+            number_at,
+        }
+    }
+
+    /// Indices of the distinct numbers around a cell
+    fn numbers_around(&self, position: &Coordinates) -> Vec<usize> {
+        let mut numbers = Vec::with_capacity(2);
+        for neighbor in position.surrounding(&self.grid.size) {
+            if let Some(number) = self.number_at[&neighbor] {
+                if !numbers.contains(&number) {
+                    numbers.push(number);
+                }
             }
         }
+        numbers
     }
-    stars
-        .into_iter()
-        .filter_map(|(_, gears)| {
-            if gears.len() == 2 {
-                Some(gears.into_iter().product::<u32>())
-            } else {
-                None
+
+    /// Numbers adjacent to at least one symbol, each counted once
+    pub fn part_numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        let mut is_part = vec![false; self.numbers.len()];
+        for symbol in self.grid.positions(is_symbol) {
+            for number in self.numbers_around(&symbol) {
+                is_part[number] = true;
             }
-        })
+        }
+        self.numbers
+            .iter()
+            .zip(is_part)
+            .filter_map(|(number, is_part)| is_part.then_some(*number))
+    }
+
+    /// Symbols adjacent to at least one number
+    pub fn connected_symbols(&self) -> impl Iterator<Item = (Coordinates, u8)> + '_ {
+        self.grid
+            .positions(is_symbol)
+            .filter(|symbol| {
+                symbol
+                    .surrounding(&self.grid.size)
+                    .any(|neighbor| self.number_at[&neighbor].is_some())
+            })
+            .map(|symbol| {
+                let cell = self.grid[&symbol];
+                (symbol, cell)
+            })
+    }
+
+    /// Every gear with the numbers around it
+    pub fn gears<'a>(
+        &'a self,
+        rule: &'a GearRule,
+    ) -> impl Iterator<Item = (Coordinates, Vec<u32>)> + 'a {
+        self.connected_symbols()
+            .filter(|(_, cell)| rule.symbols.contains(cell))
+            .filter_map(|(symbol, _)| {
+                let numbers = self.numbers_around(&symbol);
+                (numbers.len() == rule.adjacent_numbers).then(|| {
+                    let values = numbers
+                        .into_iter()
+                        .map(|number| self.numbers[number])
+                        .collect();
+                    (symbol, values)
+                })
+            })
+    }
+}
+
+pub fn part_one(input: &str) -> u32 {
+    Schematic::new(input).part_numbers().sum()
+}
+
+pub fn part_two(input: &str) -> u32 {
+    Schematic::new(input)
+        .gears(&STAR_BETWEEN_TWO_NUMBERS)
+        .map(|(_, numbers)| numbers.into_iter().product::<u32>())
         .sum()
 }
 
@@ -96,4 +146,39 @@ mod tests {
         let input = read_to_string("examples/03/1").unwrap();
         assert_eq!(part_two(&input), 467835);
     }
+
+    #[test]
+    fn test_gear_rules() {
+        let input = read_to_string("examples/03/1").unwrap();
+        let schematic = Schematic::new(&input);
+        assert_eq!(schematic.connected_symbols().count(), 6);
+        // stars touching a single number, and other symbols
+        let single = GearRule {
+            symbols: b"*",
+            adjacent_numbers: 1,
+        };
+        assert_eq!(
+            schematic.gears(&single).collect::<Vec<_>>(),
+            [(Coordinates { x: 3, y: 4 }, vec![617])]
+        );
+        let other_symbols = GearRule {
+            symbols: b"#+$",
+            adjacent_numbers: 1,
+        };
+        assert_eq!(
+            schematic
+                .gears(&other_symbols)
+                .map(|(_, numbers)| numbers[0])
+                .collect::<Vec<_>>(),
+            [633, 592, 664]
+        );
+
+        // a number touching the same symbol twice counts once
+        let schematic = Schematic::new("12.\n.*.\n.34\n");
+        assert_eq!(schematic.part_numbers().collect::<Vec<_>>(), [12, 34]);
+        assert_eq!(
+            schematic.gears(&STAR_BETWEEN_TWO_NUMBERS).next().unwrap().1,
+            [12, 34]
+        );
+    }
 }
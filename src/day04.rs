@@ -1,50 +1,131 @@
-use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
-use crate::regex;
+use crate::scanner::Scanner;
 
-pub fn part_one(input: &str) -> u32 {
-    regex!(r"(?m)^Card +(?P<card>\d+): +(?P<winning>[\d ]+) +\| +(?P<numbers>[\d ]+)$")
-        .captures_iter(input)
-        .map(|caps| {
-            let winning = regex!(r"(\d+)")
-                .captures_iter(&caps["winning"])
-                .map(|caps| caps[1].parse::<u32>().unwrap())
-                .collect::<HashSet<_>>();
-            let matches = regex!(r"(\d+)")
-                .captures_iter(&caps["numbers"])
-                .map(|caps| caps[1].parse::<u32>().unwrap())
-                .filter(|number| winning.contains(number))
-                .count() as u32;
-            if matches > 0 {
-                2u32.pow(matches - 1)
-            } else {
-                0
-            }
+pub struct Card {
+    pub id: u32,
+    /// Sorted, so that `matches` can look numbers up without allocating
+    pub winning: Vec<u32>,
+    pub numbers: Vec<u32>,
+}
+
+impl FromStr for Card {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut card = Scanner::new(s);
+        if !card.token("Card") {
+            return Err(());
+        }
+        card.skip_spaces();
+        let id = card.unsigned().ok_or(())?;
+        if !card.token(":") {
+            return Err(());
+        }
+        let mut winning = card.unsigneds().collect::<Vec<_>>();
+        winning.sort_unstable();
+        card.skip_spaces();
+        if !card.token("|") {
+            return Err(());
+        }
+        let numbers = card.unsigneds().collect();
+        if !card.is_empty() {
+            return Err(());
+        }
+        Ok(Self {
+            id,
+            winning,
+            numbers,
         })
-        .sum()
+    }
 }
 
-pub fn part_two(input: &str) -> u32 {
-    let mut copies = HashMap::<usize, u32>::new();
-    for caps in regex!(r"(?m)^Card +(?P<card>\d+): +(?P<winning>[\d ]+) +\| +(?P<numbers>[\d ]+)$")
-        .captures_iter(input)
-    {
-        let card = caps["card"].parse::<usize>().unwrap();
-        let card_count = *copies.entry(card).or_insert(1);
-        let winning = regex!(r"(\d+)")
-            .captures_iter(&caps["winning"])
-            .map(|caps| caps[1].parse::<u32>().unwrap())
-            .collect::<HashSet<_>>();
-        let matches = regex!(r"(\d+)")
-            .captures_iter(&caps["numbers"])
-            .map(|caps| caps[1].parse::<u32>().unwrap())
-            .filter(|number| winning.contains(number))
-            .count();
-        for i in 0..matches {
-            *copies.entry(card + 1 + i).or_insert(1) += card_count;
+impl Card {
+    pub fn matches(&self) -> usize {
+        self.numbers
+            .iter()
+            .filter(|number| self.winning.binary_search(number).is_ok())
+            .count()
+    }
+}
+
+/// The original rule, one point for the first match then doubled for each other
+pub fn doubling(matches: usize) -> u32 {
+    if matches > 0 {
+        2u32.pow(matches as u32 - 1)
+    } else {
+        0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoring {
+    pub id: u32,
+    pub matches: usize,
+    pub points: u32,
+    /// The original and the won copies
+    pub copies: u32,
+}
+
+/// Copies that a card won of cards past the last one, which don't exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    pub id: u32,
+    /// How many of the cards it won are missing
+    pub missing_cards: usize,
+    /// How many copies of each of them were lost
+    pub copies: u32,
+}
+
+/// Score every card with `points`, and let them win copies of the next ones
+pub fn simulate(cards: &[Card], points: impl Fn(usize) -> u32) -> (Vec<Scoring>, Vec<Overflow>) {
+    let mut scorings = cards
+        .iter()
+        .map(|card| {
+            let matches = card.matches();
+            Scoring {
+                id: card.id,
+                matches,
+                points: points(matches),
+                copies: 1,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut overflows = Vec::new();
+    for index in 0..scorings.len() {
+        let Scoring {
+            id,
+            matches,
+            copies,
+            ..
+        } = scorings[index];
+        let won = (index + 1)..(index + 1 + matches);
+        if won.end > scorings.len() {
+            overflows.push(Overflow {
+                id,
+                missing_cards: won.end - won.start.max(scorings.len()),
+                copies,
+            });
+        }
+        for next in won.start..won.end.min(scorings.len()) {
+            scorings[next].copies += copies;
         }
     }
-    copies.values().sum()
+    (scorings, overflows)
+}
+
+fn parse(input: &str) -> Vec<Card> {
+    input.lines().map(|line| line.parse().unwrap()).collect()
+}
+
+pub fn part_one(input: &str) -> u32 {
+    let (scorings, _) = simulate(&parse(input), doubling);
+    scorings.iter().map(|scoring| scoring.points).sum()
+}
+
+pub fn part_two(input: &str) -> u32 {
+    let (scorings, _) = simulate(&parse(input), doubling);
+    scorings.iter().map(|scoring| scoring.copies).sum()
 }
 
 #[cfg(test)]
@@ -64,4 +145,49 @@ mod tests {
         let input = read_to_string("examples/04/1").unwrap();
         assert_eq!(part_two(&input), 30);
     }
+
+    #[test]
+    fn test_simulation() {
+        let input = read_to_string("examples/04/1").unwrap();
+        let (scorings, overflows) = simulate(&parse(&input), |matches| matches as u32);
+        assert_eq!(
+            scorings[1],
+            Scoring {
+                id: 2,
+                matches: 2,
+                points: 2,
+                copies: 2
+            }
+        );
+        assert_eq!(
+            scorings
+                .iter()
+                .map(|scoring| scoring.copies)
+                .collect::<Vec<_>>(),
+            [1, 2, 4, 8, 14, 1]
+        );
+        assert!(overflows.is_empty());
+
+        // the last cards win copies of cards that don't exist
+        let input = "Card 1: 1 2 | 1 2\nCard 2: 3 4 | 3 5\n";
+        let (scorings, overflows) = simulate(&parse(input), doubling);
+        assert_eq!(scorings[1].copies, 2);
+        assert_eq!(
+            overflows,
+            [
+                Overflow {
+                    id: 1,
+                    missing_cards: 1,
+                    copies: 1
+                },
+                Overflow {
+                    id: 2,
+                    missing_cards: 1,
+                    copies: 2
+                }
+            ]
+        );
+        assert!("Card 1: 1 2 | 1 x".parse::<Card>().is_err());
+        assert_eq!("Card 1: 200 | 200 72".parse::<Card>().unwrap().matches(), 1);
+    }
 }
@@ -1,71 +1,86 @@
-use std::collections::HashMap;
-
 use num::integer::lcm;
 
+use crate::interner::Interner;
+use crate::parse;
 use crate::regex;
 
-pub fn part_one(input: &str) -> usize {
-    let caps =
-        regex!(r"(?P<instructions>[LR]+)\n\n(?P<nodes>(?:[A-Z]{3} = \([A-Z]{3}, [A-Z]{3}\)\n)+)")
-            .captures(input)
-            .unwrap();
-    let nodes = regex!(r"(?P<node>[A-Z]{3}) = \((?P<left>[A-Z]{3}), (?P<right>[A-Z]{3})\)")
-        .captures_iter(&caps["nodes"])
-        .map(|caps| {
-            (
-                caps["node"].to_owned(),
-                (caps["left"].to_owned(), caps["right"].to_owned()),
-            )
-        })
-        .collect::<HashMap<_, _>>();
-    let mut current_node = "AAA";
-    for (step, instruction) in caps["instructions"].chars().cycle().enumerate() {
-        if current_node == "ZZZ" {
-            return step;
+struct Network<'a> {
+    instructions: &'a str,
+    names: Interner,
+    // (left, right), indexed by node id
+    nodes: Vec<(usize, usize)>,
+}
+
+impl<'a> Network<'a> {
+    fn parse(input: &'a str) -> Self {
+        let caps = parse::captures(
+            regex!(
+                r"(?P<instructions>[LR]+)\n\n(?P<nodes>(?:[A-Z0-9]{3} = \([A-Z0-9]{3}, [A-Z0-9]{3}\)\n)+)"
+            ),
+            input,
+            "instructions, a blank line, then one node per line",
+        )
+        .unwrap();
+        let mut names = Interner::new();
+        let edges =
+            regex!(r"(?P<node>[A-Z0-9]{3}) = \((?P<left>[A-Z0-9]{3}), (?P<right>[A-Z0-9]{3})\)")
+                .captures_iter(&caps["nodes"])
+                .map(|caps| {
+                    (
+                        names.intern(&caps["node"]),
+                        names.intern(&caps["left"]),
+                        names.intern(&caps["right"]),
+                    )
+                })
+                .collect::<Vec<_>>();
+        let mut nodes = vec![(0, 0); names.len()];
+        for (node, left, right) in edges {
+            nodes[node] = (left, right);
+        }
+        Self {
+            instructions: caps.name("instructions").unwrap().as_str(),
+            names,
+            nodes,
         }
-        let (left, right) = &nodes[&current_node.to_owned()];
-        current_node = match instruction {
-            'L' => left,
-            'R' => right,
-            _ => unreachable!(),
-        };
     }
-    unreachable!()
+
+    /// Number of steps from `start` to the first node whose id satisfies `is_end`
+    fn steps(&self, start: usize, is_end: impl Fn(usize) -> bool) -> usize {
+        let mut current_node = start;
+        for (step, instruction) in self.instructions.chars().cycle().enumerate() {
+            if is_end(current_node) {
+                return step;
+            }
+            let (left, right) = self.nodes[current_node];
+            current_node = match instruction {
+                'L' => left,
+                'R' => right,
+                _ => unreachable!(),
+            };
+        }
+        unreachable!()
+    }
+}
+
+pub fn part_one(input: &str) -> usize {
+    let network = Network::parse(input);
+    let end = network.names.get("ZZZ").unwrap();
+    network.steps(network.names.get("AAA").unwrap(), |node| node == end)
 }
 
 pub fn part_two(input: &str) -> usize {
-    let caps = regex!(
-        r"(?P<instructions>[LR]+)\n\n(?P<nodes>(?:[A-Z0-9]{3} = \([A-Z0-9]{3}, [A-Z0-9]{3}\)\n)+)"
-    )
-    .captures(input)
-    .unwrap();
-    let nodes =
-        regex!(r"(?P<node>[A-Z0-9]{3}) = \((?P<left>[A-Z0-9]{3}), (?P<right>[A-Z0-9]{3})\)")
-            .captures_iter(&caps["nodes"])
-            .map(|caps| {
-                (
-                    caps["node"].to_owned(),
-                    (caps["left"].to_owned(), caps["right"].to_owned()),
-                )
-            })
-            .collect::<HashMap<_, _>>();
-    nodes
-        .keys()
-        .filter(|node| node.ends_with('A'))
-        .map(|mut current_node| {
-            for (step, instruction) in caps["instructions"].chars().cycle().enumerate() {
-                if current_node.ends_with('Z') {
-                    return step;
-                }
-                let (left, right) = &nodes[&current_node.to_owned()];
-                current_node = match instruction {
-                    'L' => left,
-                    'R' => right,
-                    _ => unreachable!(),
-                };
-            }
-            unreachable!()
-        })
+    let network = Network::parse(input);
+    let is_end = network
+        .names
+        .names()
+        .map(|name| name.ends_with('Z'))
+        .collect::<Vec<_>>();
+    network
+        .names
+        .names()
+        .enumerate()
+        .filter(|(_, name)| name.ends_with('A'))
+        .map(|(start, _)| network.steps(start, |node| is_end[node]))
         .reduce(lcm)
         .unwrap()
 }
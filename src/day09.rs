@@ -1,15 +1,12 @@
 use itertools::Itertools;
 
-use crate::regex;
+use crate::scanner;
 
 fn parse_and_extrapolate(input: &str) -> impl Iterator<Item = Vec<Vec<i32>>> + '_ {
-    regex!(r"(?P<sequence>(?:[-\d ])+)")
-        .captures_iter(input)
-        .map(|caps| {
-            let mut sequences = vec![regex!(r"(?P<number>-?\d+)")
-                .captures_iter(&caps["sequence"])
-                .map(|caps| caps["number"].parse::<i32>().unwrap())
-                .collect::<Vec<_>>()];
+    scanner::lines(input)
+        .filter(|line| !line.is_empty())
+        .map(|mut line| {
+            let mut sequences = vec![line.signeds::<i32>().collect::<Vec<_>>()];
             while sequences.last().unwrap().iter().all_equal_value() != Ok(&0) {
                 sequences.push(
                     sequences
@@ -1,7 +1,4 @@
-use std::cmp::Ordering::{Greater, Less};
-use std::ops::{Index, IndexMut};
-
-use itertools::Itertools;
+use crate::grid::{BitGrid, Cells, Coordinates, Direction, Grid};
 
 #[derive(Clone, Copy, PartialEq)]
 enum Pipe {
@@ -36,6 +33,15 @@ impl Pipe {
         self == &Self::EW || self == &Self::NE || self == &Self::SE
     }
 
+    fn is_connected_to(&self, direction: &Direction) -> bool {
+        match direction {
+            Direction::Up => self.is_connected_to_north(),
+            Direction::Right => self.is_connected_to_east(),
+            Direction::Down => self.is_connected_to_south(),
+            Direction::Left => self.is_connected_to_west(),
+        }
+    }
+
     fn separates_north_west_and_south_east(&self) -> bool {
         self == &Self::NS || self == &Self::EW || self == &Self::NW || self == &Self::SE
     }
@@ -64,234 +70,74 @@ impl From<char> for Tile {
     }
 }
 
-#[derive(Clone, Copy)]
-struct Coordinates(usize, usize);
-
-impl Coordinates {
-    fn north(&self) -> Self {
-        Self(self.0, self.1 - 1)
-    }
-
-    fn west(&self) -> Self {
-        Self(self.0 - 1, self.1)
-    }
-
-    fn south(&self) -> Self {
-        Self(self.0, self.1 + 1)
-    }
-
-    fn east(&self) -> Self {
-        Self(self.0 + 1, self.1)
+impl Tile {
+    fn is_connected_to(&self, direction: &Direction) -> bool {
+        match self {
+            Self::Pipe(pipe) => pipe.is_connected_to(direction),
+            _ => false,
+        }
     }
 }
 
-struct Grid {
-    tiles: Vec<Vec<Tile>>,
-    start: Coordinates,
-    size: Coordinates,
-}
-
-impl Index<Coordinates> for Grid {
-    type Output = Tile;
-
-    fn index(&self, index: Coordinates) -> &Self::Output {
-        self.tiles.index(index.1).index(index.0)
-    }
+/// Parse the grid and replace the start tile with the only pipe that fits its neighbors
+fn parse(input: &str) -> (Grid<Tile>, Coordinates) {
+    let mut grid = Grid::from_iter(input.lines().map(|line| line.chars().map(Tile::from)));
+    let start = grid.position(|tile| tile == &Tile::Start).unwrap();
+    let mut connections = [false; 4];
+    for (_, direction, tile) in grid.neighbors_of(&start) {
+        connections[direction.clone() as usize] = tile.is_connected_to(&direction.reverse());
+    }
+    // Up, Right, Down, Left
+    grid[&start] = Tile::Pipe(match connections {
+        [true, false, true, false] => Pipe::NS,
+        [false, true, false, true] => Pipe::EW,
+        [true, true, false, false] => Pipe::NE,
+        [true, false, false, true] => Pipe::NW,
+        [false, false, true, true] => Pipe::SW,
+        [false, true, true, false] => Pipe::SE,
+        _ => unreachable!(),
+    });
+    (grid, start)
 }
 
-impl IndexMut<Coordinates> for Grid {
-    fn index_mut(&mut self, index: Coordinates) -> &mut Self::Output {
-        self.tiles.index_mut(index.1).index_mut(index.0)
-    }
+/// Walk along the loop from the start and mark every tile on it
+fn main_loop(grid: &Grid<Tile>, start: Coordinates) -> BitGrid {
+    let mut in_loop = BitGrid::new(&grid.size);
+    let mut direction = Direction::iter()
+        .find(|direction| grid[&start].is_connected_to(direction))
+        .unwrap()
+        .clone();
+    let mut position = start;
+    while in_loop.set(&position) {
+        position = position.next(&direction, &grid.size).unwrap();
+        direction = Direction::iter()
+            .find(|next| next != &&direction.reverse() && grid[&position].is_connected_to(next))
+            .unwrap()
+            .clone();
+    }
+    in_loop
 }
 
-impl Grid {
-    fn parse(input: &str) -> Self {
-        let tiles = input
-            .lines()
-            .map(|line| line.chars().map(Tile::from).collect::<Vec<_>>())
-            .collect::<Vec<_>>();
-        assert!(tiles.iter().map(|row| row.len()).all_equal());
-        let size = Coordinates(tiles[0].len(), tiles.len());
-        for (y, row) in tiles.iter().enumerate() {
-            for (x, tile) in row.iter().enumerate() {
-                if tile == &Tile::Start {
-                    return Self {
-                        tiles,
-                        start: Coordinates(x, y),
-                        size,
-                    };
-                }
-            }
-        }
-        unreachable!()
-    }
-
-    fn costs(&self) -> Vec<Vec<usize>> {
-        fn recursion(
-            grid: &Grid,
-            costs: &mut Vec<Vec<usize>>,
-            current: Coordinates,
-            previous: Coordinates,
-        ) {
-            if let Tile::Pipe(pipe) = grid[current] {
-                costs[current.1][current.0] =
-                    costs[current.1][current.0].min(costs[previous.1][previous.0] + 1);
-                match (pipe, current.0.cmp(&previous.0), current.1.cmp(&previous.1)) {
-                    (Pipe::NS, _, Greater) | (Pipe::SW, Greater, _) | (Pipe::SE, Less, _) => {
-                        recursion(grid, costs, current.south(), current)
-                    }
-                    (Pipe::EW, Greater, _) | (Pipe::NE, _, Greater) | (Pipe::SE, _, Less) => {
-                        recursion(grid, costs, current.east(), current)
-                    }
-                    (Pipe::NS, _, Less) | (Pipe::NE, Less, _) | (Pipe::NW, Greater, _) => {
-                        recursion(grid, costs, current.north(), current)
-                    }
-                    (Pipe::EW, Less, _) | (Pipe::NW, _, Greater) | (Pipe::SW, _, Less) => {
-                        recursion(grid, costs, current.west(), current)
-                    }
-                    _ => unreachable!(),
-                }
-            }
-        }
-
-        let mut costs = vec![vec![usize::MAX; self.size.0]; self.size.1];
-        costs[self.start.1][self.start.0] = 0;
-        if self.start.1 > 0 {
-            let south = self.start.north();
-            if let Tile::Pipe(pipe) = self[south] {
-                if pipe.is_connected_to_south() {
-                    recursion(self, &mut costs, south, self.start);
-                }
-            }
-        }
-        if self.start.0 > 0 {
-            let west = self.start.west();
-            if let Tile::Pipe(pipe) = self[west] {
-                if pipe.is_connected_to_east() {
-                    recursion(self, &mut costs, west, self.start);
-                }
-            }
-        }
-        if self.start.1 < self.size.1 {
-            let south = self.start.south();
-            if let Tile::Pipe(pipe) = self[south] {
-                if pipe.is_connected_to_north() {
-                    recursion(self, &mut costs, south, self.start);
-                }
-            }
+fn stripped_pipes(grid: Grid<Tile>, start: Coordinates) -> Vec<Vec<Option<Pipe>>> {
+    let in_loop = main_loop(&grid, start);
+    let mut pipes = vec![vec![None; grid.size.x]; grid.size.y];
+    for (position, tile) in &grid {
+        if let (true, Tile::Pipe(pipe)) = (in_loop.test(&position), tile) {
+            pipes[position.y][position.x] = Some(*pipe);
         }
-        if self.start.0 < self.size.0 {
-            let east = self.start.east();
-            if let Tile::Pipe(pipe) = self[east] {
-                if pipe.is_connected_to_west() {
-                    recursion(self, &mut costs, east, self.start);
-                }
-            }
-        }
-        costs
-    }
-
-    fn stripped_pipes(self) -> Vec<Vec<Option<Pipe>>> {
-        let costs = self.costs();
-
-        let is_connected_to_north = {
-            if self.start.1 > 0 {
-                if let Tile::Pipe(pipe) = self[self.start.north()] {
-                    pipe.is_connected_to_south()
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        };
-        let is_connected_to_west = {
-            if self.start.0 > 0 {
-                if let Tile::Pipe(pipe) = self[self.start.west()] {
-                    pipe.is_connected_to_east()
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        };
-        let is_connected_to_south = {
-            if self.start.1 < self.size.1 {
-                if let Tile::Pipe(pipe) = self[self.start.south()] {
-                    pipe.is_connected_to_north()
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        };
-        let is_connected_to_east = {
-            if self.start.0 < self.size.0 {
-                if let Tile::Pipe(pipe) = self[self.start.east()] {
-                    pipe.is_connected_to_west()
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        };
-        let start = match (
-            is_connected_to_north,
-            is_connected_to_west,
-            is_connected_to_south,
-            is_connected_to_east,
-        ) {
-            (true, false, true, false) => Pipe::NS,
-            (false, true, false, true) => Pipe::EW,
-            (true, false, false, true) => Pipe::NE,
-            (true, true, false, false) => Pipe::NW,
-            (false, true, true, false) => Pipe::SW,
-            (false, false, true, true) => Pipe::SE,
-            _ => unreachable!(),
-        };
-
-        self.tiles
-            .into_iter()
-            .enumerate()
-            .map(|(y, row)| {
-                row.into_iter()
-                    .zip(&costs[y])
-                    .map(|(tile, cost)| {
-                        if cost < &usize::MAX {
-                            if let Tile::Pipe(pipe) = tile {
-                                Some(pipe)
-                            } else if tile == Tile::Start {
-                                Some(start)
-                            } else {
-                                unreachable!()
-                            }
-                        } else {
-                            None
-                        }
-                    })
-                    .collect()
-            })
-            .collect()
     }
+    pipes
 }
 
 pub fn part_one(input: &str) -> usize {
-    Grid::parse(input)
-        .costs()
-        .into_iter()
-        .flatten()
-        .filter(|cost| cost < &usize::MAX)
-        .max()
-        .unwrap()
+    let (grid, start) = parse(input);
+    // the farthest tile is halfway around the loop
+    main_loop(&grid, start).count() / 2
 }
 
 pub fn part_two(input: &str) -> usize {
-    let mut pipes = Grid::parse(input)
-        .stripped_pipes()
+    let (grid, start) = parse(input);
+    let mut pipes = stripped_pipes(grid, start)
         .into_iter()
         .map(|row| row.iter().map(|tile| (*tile, false)).collect::<Vec<_>>())
         .collect::<Vec<_>>();
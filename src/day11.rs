@@ -1,64 +1,67 @@
-use std::collections::HashSet;
-
 use itertools::Itertools;
 
-fn parse_galaxies(input: &str) -> Vec<(usize, usize)> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.chars()
-                .enumerate()
-                .filter_map(move |(x, char)| if char == '#' { Some((x, y)) } else { None })
-        })
-        .collect()
+use crate::grid::{Cells, Coordinates, Grid, SparseGrid};
+
+fn parse_galaxies(input: &str) -> SparseGrid<bool> {
+    SparseGrid::from_grid(
+        Grid::from_iter(
+            input
+                .lines()
+                .map(|line| line.chars().map(|char| char == '#')),
+        ),
+        false,
+    )
 }
 
-fn shift(galaxies: &mut [(usize, usize)], expansion: usize) {
-    let non_empty_columns = galaxies.iter().map(|(x, _)| *x).collect::<HashSet<_>>();
-    let non_empty_rows = galaxies.iter().map(|(_, y)| *y).collect::<HashSet<_>>();
-    let mut column_shift = 0;
-    for empty_column in
-        (1..*non_empty_columns.iter().max().unwrap()).filter(|x| !non_empty_columns.contains(x))
-    {
-        for galaxy in galaxies.iter_mut() {
-            if galaxy.0 > empty_column + column_shift {
-                galaxy.0 += expansion;
-            }
-        }
-        column_shift += expansion;
+/// Number of empty lines before each index, given the indices of the non-empty lines
+fn empty_before(non_empty: impl Iterator<Item = usize>, size: usize) -> Vec<usize> {
+    let mut is_empty = vec![true; size];
+    for index in non_empty {
+        is_empty[index] = false;
     }
-    let mut row_shift = 0;
-    for empty_row in
-        (1..*non_empty_rows.iter().max().unwrap()).filter(|y| !non_empty_rows.contains(y))
-    {
-        for galaxy in galaxies.iter_mut() {
-            if galaxy.1 > empty_row + row_shift {
-                galaxy.1 += expansion;
+    is_empty
+        .into_iter()
+        .scan(0, |empty_count, is_empty| {
+            let before = *empty_count;
+            if is_empty {
+                *empty_count += 1;
             }
-        }
-        row_shift += expansion;
+            Some(before)
+        })
+        .collect()
+}
+
+fn shift(galaxies: &SparseGrid<bool>, expansion: usize) -> SparseGrid<bool> {
+    let size = galaxies.size();
+    let empty_columns_before = empty_before(galaxies.iter().map(|(galaxy, _)| galaxy.x), size.x);
+    let empty_rows_before = empty_before(galaxies.iter().map(|(galaxy, _)| galaxy.y), size.y);
+    let mut shifted = SparseGrid::new(false);
+    for (galaxy, _) in galaxies.iter() {
+        shifted.insert(
+            Coordinates {
+                x: galaxy.x + empty_columns_before[galaxy.x] * expansion,
+                y: galaxy.y + empty_rows_before[galaxy.y] * expansion,
+            },
+            true,
+        );
     }
+    shifted
 }
 
-fn distances_sum(galaxies: &[(usize, usize)]) -> usize {
+fn distances_sum(galaxies: &SparseGrid<bool>) -> usize {
     galaxies
         .iter()
         .tuple_combinations()
-        .map(|(a, b)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
+        .map(|((a, _), (b, _))| a.x.abs_diff(b.x) + a.y.abs_diff(b.y))
         .sum()
 }
 
 pub fn part_one(input: &str) -> usize {
-    let mut galaxies = parse_galaxies(input);
-    shift(&mut galaxies, 1);
-    distances_sum(&galaxies)
+    distances_sum(&shift(&parse_galaxies(input), 1))
 }
 
 pub fn part_two(input: &str) -> usize {
-    let mut galaxies = parse_galaxies(input);
-    shift(&mut galaxies, 999999);
-    distances_sum(&galaxies)
+    distances_sum(&shift(&parse_galaxies(input), 999999))
 }
 
 #[cfg(test)]
@@ -76,13 +79,8 @@ mod tests {
     #[test]
     fn test_part_two() {
         let input = read_to_string("examples/11/1").unwrap();
-
-        let mut galaxies = parse_galaxies(&input);
-        shift(&mut galaxies, 9);
-        assert_eq!(distances_sum(&galaxies), 1030);
-
-        let mut galaxies = parse_galaxies(&input);
-        shift(&mut galaxies, 99);
-        assert_eq!(distances_sum(&galaxies), 8410);
+        let galaxies = parse_galaxies(&input);
+        assert_eq!(distances_sum(&shift(&galaxies, 9)), 1030);
+        assert_eq!(distances_sum(&shift(&galaxies, 99)), 8410);
     }
 }
@@ -1,5 +1,6 @@
 use cached::proc_macro::cached;
 
+use crate::cache::{self, CacheStats};
 use crate::regex;
 
 fn parse(input: &str, fold: usize) -> impl Iterator<Item = (Vec<u8>, Vec<usize>)> + '_ {
@@ -42,6 +43,10 @@ fn count_possibilities(row: &[u8], groups: &[usize]) -> usize {
     result
 }
 
+pub fn cache_stats() -> Vec<CacheStats> {
+    vec![cache::stats("count_possibilities", &COUNT_POSSIBILITIES)]
+}
+
 pub fn part_one(input: &str) -> usize {
     parse(input, 1)
         .map(|(mut row, groups)| {
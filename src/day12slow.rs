@@ -1,9 +1,11 @@
 // Naive backtracking solution
 // Part 2 is solved in over 24h parallelized on a desktop CPU with 8 cores and 16 threads
+// Set AOC_CACHE_DIR to keep the count of each finished row, so that an interrupted run can resume
 
 use itertools::Itertools;
 use regex::bytes::Regex;
 
+use crate::cache::{hash, persisted, PersistentStore};
 use crate::regex;
 
 fn groups_regex(groups: &[usize]) -> Regex {
@@ -67,16 +69,22 @@ fn count_possibilities(mut row: Vec<u8>, regex: &Regex) -> usize {
     }
 }
 
-pub fn part_one(input: &str) -> usize {
-    parse(input, 1)
-        .map(|(row, regex)| count_possibilities(row, &regex))
+fn count_all(input: &str, fold: usize) -> usize {
+    let mut store = PersistentStore::open("day12slow-count_possibilities", 2).unwrap();
+    parse(input, fold)
+        .map(|(row, regex)| {
+            let key = hash((&row, regex.as_str()));
+            persisted(&mut store, key, || count_possibilities(row, &regex))
+        })
         .sum()
 }
 
+pub fn part_one(input: &str) -> usize {
+    count_all(input, 1)
+}
+
 pub fn part_two(input: &str) -> usize {
-    parse(input, 5)
-        .map(|(row, regex)| count_possibilities(row, &regex))
-        .sum()
+    count_all(input, 5)
 }
 
 #[cfg(test)]
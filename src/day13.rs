@@ -1,137 +1,55 @@
-use std::collections::HashSet;
-
-use itertools::Itertools;
-
+use crate::grid::Grid;
 use crate::regex;
 
-fn parse(input: &str) -> impl Iterator<Item = Vec<Vec<u8>>> + '_ {
+fn parse(input: &str) -> impl Iterator<Item = Grid<u8>> + '_ {
     regex!(r"(?P<pattern>(?:[.#]+\n)+)")
         .captures_iter(input)
         .map(|caps| {
-            regex!(r"(?P<line>[.#]+)")
-                .captures_iter(&caps["pattern"])
-                .map(|caps| caps["line"].as_bytes().to_vec())
-                .collect::<Vec<_>>()
+            Grid::from_iter(
+                regex!(r"(?P<line>[.#]+)")
+                    .captures_iter(&caps["pattern"])
+                    .map(|caps| caps["line"].as_bytes().to_vec()),
+            )
         })
 }
 
-fn is_valid_reflection(line: &[u8], position: usize) -> bool {
-    line[..(position + 1)]
-        .iter()
-        .rev()
-        .zip(line[(position + 1)..].iter())
-        .all(|(a, b)| a == b)
-}
-
-fn refine_reflections(line: &[u8], positions: &mut Vec<usize>) {
-    positions.retain(|position| is_valid_reflection(line, *position));
-}
-
-fn find_horizontal_reflection(pattern: &[Vec<u8>]) -> Option<usize> {
-    let mut reflections = (0..(pattern[0].len() - 1)).collect::<Vec<_>>();
-    for line in pattern {
-        refine_reflections(line, &mut reflections);
-        if reflections.is_empty() {
-            return None;
-        }
-    }
-    Some(reflections.into_iter().exactly_one().unwrap())
+/// Number of lines before a mirror that makes every line match its reflection, except for
+/// exactly `smudges` cells
+fn find_reflection<'a, L>(lines: &[L], smudges: usize) -> Option<usize>
+where
+    L: IntoIterator<Item = &'a u8> + Clone,
+{
+    (1..lines.len()).find(|position| {
+        (0..*position)
+            .rev()
+            .zip(*position..lines.len())
+            .map(|(a, b)| {
+                lines[a]
+                    .clone()
+                    .into_iter()
+                    .zip(lines[b].clone())
+                    .filter(|(a, b)| a != b)
+                    .count()
+            })
+            .sum::<usize>()
+            == smudges
+    })
 }
 
-fn rotated(pattern: &[Vec<u8>]) -> Vec<Vec<u8>> {
-    let mut rotated_pattern = vec![Vec::new(); pattern[0].len()];
-    for line in pattern {
-        for x in 0..line.len() {
-            rotated_pattern[x].push(line[x]);
-        }
-    }
-    rotated_pattern
-}
-
-fn find_vertical_reflection(pattern: &[Vec<u8>]) -> Option<usize> {
-    find_horizontal_reflection(&rotated(pattern))
-}
-
-fn find_reflection(pattern: &[Vec<u8>]) -> (usize, bool) {
-    if let Some(position) = find_horizontal_reflection(pattern) {
-        (position, false)
+fn summarize(pattern: &Grid<u8>, smudges: usize) -> usize {
+    if let Some(position) = find_reflection(&pattern.rows().collect::<Vec<_>>(), smudges) {
+        100 * position
     } else {
-        (find_vertical_reflection(pattern).unwrap(), true)
+        find_reflection(&pattern.columns().collect::<Vec<_>>(), smudges).unwrap()
     }
 }
 
 pub fn part_one(input: &str) -> usize {
-    parse(input)
-        .map(|pattern| {
-            let (position, vertical) = find_reflection(&pattern);
-            (position + 1) * if vertical { 100 } else { 1 }
-        })
-        .sum()
-}
-
-fn refine_reflections_with_smudge(line: &[u8], positions: &mut Vec<usize>) {
-    let mut reflections = HashSet::new();
-    for smudge in 0..line.len() {
-        let mut line = line.to_vec();
-        line[smudge] = if line[smudge] == b'.' { b'#' } else { b'.' };
-        for position in 0..(line.len() - 1) {
-            if is_valid_reflection(&line, position) {
-                reflections.insert(position);
-            }
-        }
-    }
-    positions.retain(|position| reflections.contains(position));
-}
-
-fn find_horizontal_reflections_with_smudge(pattern: &[Vec<u8>]) -> Vec<usize> {
-    let mut reflections = Vec::new();
-    for smudge in 0..pattern.len() {
-        let mut smudge_reflections = (0..(pattern[0].len() - 1)).collect::<Vec<_>>();
-        for (i, line) in pattern.iter().enumerate() {
-            if i == smudge {
-                refine_reflections_with_smudge(line, &mut smudge_reflections);
-            } else {
-                refine_reflections(line, &mut smudge_reflections);
-            }
-        }
-        reflections.extend(smudge_reflections.into_iter());
-    }
-    reflections
-}
-
-fn find_vertical_reflections_with_smudge(pattern: &[Vec<u8>]) -> Vec<usize> {
-    find_horizontal_reflections_with_smudge(&rotated(pattern))
-}
-
-fn find_reflection_with_smudge(pattern: &[Vec<u8>]) -> (usize, bool) {
-    let reflection_without_smudge = find_reflection(pattern);
-    if let Ok(position) = find_horizontal_reflections_with_smudge(pattern)
-        .into_iter()
-        .filter(|position| reflection_without_smudge.1 || position != &reflection_without_smudge.0)
-        .exactly_one()
-    {
-        (position, false)
-    } else {
-        (
-            find_vertical_reflections_with_smudge(pattern)
-                .into_iter()
-                .filter(|position| {
-                    !reflection_without_smudge.1 || position != &reflection_without_smudge.0
-                })
-                .exactly_one()
-                .unwrap(),
-            true,
-        )
-    }
+    parse(input).map(|pattern| summarize(&pattern, 0)).sum()
 }
 
 pub fn part_two(input: &str) -> usize {
-    parse(input)
-        .map(|pattern| {
-            let (position, vertical) = find_reflection_with_smudge(&pattern);
-            (position + 1) * if vertical { 100 } else { 1 }
-        })
-        .sum()
+    parse(input).map(|pattern| summarize(&pattern, 1)).sum()
 }
 
 #[cfg(test)]
@@ -1,10 +1,10 @@
-use cached::proc_macro::cached;
+use crate::cycle;
 
 fn parse(input: &str) -> Vec<Vec<char>> {
     input.lines().map(|line| line.chars().collect()).collect()
 }
 
-fn tilt_horizontal(platform: &mut Vec<Vec<char>>, west: bool) {
+fn tilt_horizontal(platform: &mut [Vec<char>], west: bool) {
     for row in platform {
         for sub in row.split_mut(|char| char == &'#') {
             sub.sort();
@@ -24,15 +24,16 @@ fn tilt_horizontal(platform: &mut Vec<Vec<char>>, west: bool) {
 /// `....\` \
 ///
 /// Applying this twice is a no-op
-fn reflect(platform: &mut Vec<Vec<char>>) {
-    for y in 0..platform.len() {
-        for x in 0..y {
-            (platform[y][x], platform[x][y]) = (platform[x][y], platform[y][x]);
+fn reflect(platform: &mut [Vec<char>]) {
+    for y in 1..platform.len() {
+        let (above, below) = platform.split_at_mut(y);
+        for (x, row) in above.iter_mut().enumerate() {
+            std::mem::swap(&mut row[y], &mut below[0][x]);
         }
     }
 }
 
-fn tilt_vertical(platform: &mut Vec<Vec<char>>, north: bool) {
+fn tilt_vertical(platform: &mut [Vec<char>], north: bool) {
     reflect(platform);
     tilt_horizontal(platform, north);
     reflect(platform)
@@ -53,25 +54,19 @@ pub fn part_one(input: &str) -> usize {
     north_load(platform)
 }
 
-#[cached(key = "u64", convert = "{ crate::cache::hash((&platform, count)) }")]
-fn cycled(mut platform: Vec<Vec<char>>, count: usize) -> Vec<Vec<char>> {
-    if count == 1 {
-        tilt_vertical(&mut platform, true);
-        tilt_horizontal(&mut platform, true);
-        tilt_vertical(&mut platform, false);
-        tilt_horizontal(&mut platform, false);
-        platform
-    } else {
-        let count = count / 10;
-        for _ in 0..10 {
-            platform = cycled(platform, count);
-        }
-        platform
-    }
+fn spin_cycle(platform: &[Vec<char>]) -> Vec<Vec<char>> {
+    let mut platform = platform.to_vec();
+    tilt_vertical(&mut platform, true);
+    tilt_horizontal(&mut platform, true);
+    tilt_vertical(&mut platform, false);
+    tilt_horizontal(&mut platform, false);
+    platform
 }
 
 pub fn part_two(input: &str) -> usize {
-    north_load(cycled(parse(input), 1000000000))
+    north_load(cycle::nth(parse(input), 1000000000, |platform| {
+        spin_cycle(platform)
+    }))
 }
 
 #[cfg(test)]
@@ -91,4 +86,22 @@ mod tests {
         let input = read_to_string("examples/14/1").unwrap();
         assert_eq!(part_two(&input), 64);
     }
+
+    #[test]
+    fn test_spin_cycle() {
+        let input = read_to_string("examples/14/1").unwrap();
+        // the example settles into a loop of 7 spin cycles after 3 of them
+        let expected = cycle::Cycle {
+            start: 3,
+            length: 7,
+        };
+        assert_eq!(
+            cycle::find(parse(&input), |platform| spin_cycle(platform)),
+            expected
+        );
+        assert_eq!(
+            cycle::brent(&parse(&input), |platform| spin_cycle(platform)),
+            expected
+        );
+    }
 }
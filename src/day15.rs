@@ -1,8 +1,4 @@
-use std::collections::HashMap;
-
-use itertools::Itertools;
-
-use crate::regex;
+use crate::scanner::Scanner;
 
 fn hash(string: &str) -> usize {
     let mut result = 0;
@@ -14,53 +10,44 @@ fn hash(string: &str) -> usize {
     result
 }
 
+fn steps(input: &str) -> impl Iterator<Item = &str> {
+    let mut scanner = Scanner::new(input);
+    std::iter::from_fn(move || {
+        while scanner.token(",") || scanner.token("\n") {}
+        let step = scanner.take_until(b",\n");
+        (!step.is_empty()).then_some(step)
+    })
+}
+
 pub fn part_one(input: &str) -> usize {
-    regex!(r"(?P<step>[[:ascii:]--[,]]+)[,\n]")
-        .captures_iter(input)
-        .map(|caps| hash(&caps["step"]))
-        .sum()
+    steps(input).map(hash).sum()
 }
 
 pub fn part_two(input: &str) -> usize {
-    let mut boxes = HashMap::<usize, Vec<(String, usize)>>::new();
-    for caps in regex!(r"(?P<label>[a-z]+)(?P<operation>-|(?:=\d))[,\n]").captures_iter(input) {
-        let lens_label = caps["label"].to_owned();
-        let operation = &caps["operation"];
-        let operation = if operation.starts_with('=') {
-            Some(operation.chars().nth(1).unwrap().to_digit(10).unwrap() as usize)
-        } else {
-            None
-        };
-        let box_index = hash(&lens_label);
-        match boxes.get_mut(&box_index) {
-            Some(box_content) => {
-                match box_content
-                    .iter()
-                    .find_position(|(old_lens_label, _)| old_lens_label == &lens_label)
-                {
-                    Some((position, _)) => {
-                        if let Some(focal_length) = operation {
-                            box_content[position] = (lens_label, focal_length);
-                        } else {
-                            box_content.remove(position);
-                        }
-                    }
-                    None => {
-                        if let Some(focal_length) = operation {
-                            box_content.push((lens_label, focal_length));
-                        }
-                    }
-                }
+    let mut boxes = vec![Vec::<(&str, usize)>::new(); 256];
+    for step in steps(input) {
+        let mut step = Scanner::new(step);
+        let lens_label = step.take_while(|byte| byte.is_ascii_lowercase());
+        let box_content = &mut boxes[hash(lens_label)];
+        let position = box_content
+            .iter()
+            .position(|(old_lens_label, _)| old_lens_label == &lens_label);
+        if step.token("=") {
+            let focal_length = step.unsigned().unwrap();
+            match position {
+                Some(position) => box_content[position].1 = focal_length,
+                None => box_content.push((lens_label, focal_length)),
             }
-            None => {
-                if let Some(focal_length) = operation {
-                    boxes.insert(box_index, vec![(lens_label, focal_length)]);
-                }
+        } else {
+            step.expect("-");
+            if let Some(position) = position {
+                box_content.remove(position);
             }
         }
     }
     boxes
         .into_iter()
+        .enumerate()
         .flat_map(|(box_number, lenses)| {
             lenses
                 .into_iter()
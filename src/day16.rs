@@ -1,3 +1,5 @@
+use crate::grid::{Coordinates, Direction, DirectionBitGrid, Grid};
+
 enum Mirror {
     /// `/`
     Right,
@@ -5,6 +7,7 @@ enum Mirror {
     Left,
 }
 
+#[derive(PartialEq)]
 enum Splitter {
     /// `|`
     Vertical,
@@ -17,188 +20,99 @@ enum Object {
     Splitter(Splitter),
 }
 
-type Coordinates = (usize, usize);
-
-#[derive(Clone, PartialEq)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
-impl Direction {
-    fn reflect(&self, mirror: &Mirror) -> Self {
-        match (self, mirror) {
-            (Self::Up, Mirror::Right) | (Self::Down, Mirror::Left) => Self::Right,
-            (Self::Up, Mirror::Left) | (Self::Down, Mirror::Right) => Self::Left,
-            (Self::Right, Mirror::Right) | (Self::Left, Mirror::Left) => Self::Up,
-            (Self::Right, Mirror::Left) | (Self::Left, Mirror::Right) => Self::Down,
-        }
-    }
-
-    fn split(&self, splitter: &Splitter) -> Vec<Self> {
-        match (self, splitter) {
-            (Self::Up, Splitter::Vertical)
-            | (Self::Right, Splitter::Horizontal)
-            | (Self::Down, Splitter::Vertical)
-            | (Self::Left, Splitter::Horizontal) => vec![self.clone()],
-            (Self::Up, Splitter::Horizontal) | (Self::Down, Splitter::Horizontal) => {
-                vec![Self::Left, Self::Right]
-            }
-            (Self::Right, Splitter::Vertical) | (Self::Left, Splitter::Vertical) => {
-                vec![Self::Up, Self::Down]
-            }
-        }
-    }
-
-    fn next(&self, (x, y): Coordinates, size: usize) -> Option<Coordinates> {
-        match self {
-            Self::Up => {
-                if y > 0 {
-                    Some((x, y - 1))
-                } else {
-                    None
-                }
-            }
-            Self::Right => {
-                if x < size - 1 {
-                    Some((x + 1, y))
-                } else {
-                    None
-                }
-            }
-            Self::Down => {
-                if y < size - 1 {
-                    Some((x, y + 1))
-                } else {
-                    None
-                }
-            }
-            Self::Left => {
-                if x > 0 {
-                    Some((x - 1, y))
-                } else {
-                    None
-                }
-            }
+impl Mirror {
+    fn reflect(&self, direction: &Direction) -> Direction {
+        match (self, direction.is_vertical()) {
+            (Self::Right, true) | (Self::Left, false) => direction.turn_right(),
+            (Self::Right, false) | (Self::Left, true) => direction.turn_left(),
         }
     }
 }
 
-struct Tile {
-    object: Option<Object>,
-    beams: Vec<Direction>,
-}
-
-impl From<char> for Tile {
-    fn from(value: char) -> Self {
-        Self {
-            object: match value {
-                '.' => None,
-                '/' => Some(Object::Mirror(Mirror::Right)),
-                '\\' => Some(Object::Mirror(Mirror::Left)),
-                '|' => Some(Object::Splitter(Splitter::Vertical)),
-                '-' => Some(Object::Splitter(Splitter::Horizontal)),
-                _ => unreachable!(),
-            },
-            beams: Vec::with_capacity(4),
+impl Splitter {
+    fn split(&self, direction: &Direction) -> Vec<Direction> {
+        if direction.is_vertical() == (self == &Self::Vertical) {
+            vec![direction.clone()]
+        } else {
+            vec![direction.turn_left(), direction.turn_right()]
         }
     }
 }
 
-impl Tile {
-    fn is_energized(&self) -> bool {
-        !self.beams.is_empty()
-    }
-
-    fn reset(&mut self) {
-        self.beams.truncate(0);
-    }
-}
-
-struct Grid {
-    tiles: Vec<Vec<Tile>>,
-    size: usize,
-}
-
-impl From<&str> for Grid {
-    fn from(value: &str) -> Self {
-        let tiles = value
-            .lines()
-            .map(|line| line.chars().map(Tile::from).collect::<Vec<_>>())
-            .collect::<Vec<_>>();
-        let size = tiles.len();
-        Self { tiles, size }
+impl Object {
+    fn parse(value: char) -> Option<Self> {
+        match value {
+            '.' => None,
+            '/' => Some(Object::Mirror(Mirror::Right)),
+            '\\' => Some(Object::Mirror(Mirror::Left)),
+            '|' => Some(Object::Splitter(Splitter::Vertical)),
+            '-' => Some(Object::Splitter(Splitter::Horizontal)),
+            _ => unreachable!(),
+        }
     }
 }
 
-impl Grid {
-    fn advance_beam(&mut self, coordinates: Coordinates, direction: Direction) {
-        let Tile { object, beams } = &mut self.tiles[coordinates.1][coordinates.0];
-        if !beams.contains(&direction) {
-            beams.push(direction.clone());
-            match object {
-                None => {
-                    if let Some(coordinates) = direction.next(coordinates, self.size) {
-                        self.advance_beam(coordinates, direction);
-                    }
-                }
-                Some(Object::Mirror(mirror)) => {
-                    let direction = direction.reflect(mirror);
-                    if let Some(coordinates) = direction.next(coordinates, self.size) {
-                        self.advance_beam(coordinates, direction);
-                    }
-                }
-                Some(Object::Splitter(splitter)) => {
-                    for direction in direction.split(splitter) {
-                        if let Some(coordinates) = direction.next(coordinates, self.size) {
-                            self.advance_beam(coordinates, direction);
-                        }
-                    }
-                }
+fn advance_beam(
+    grid: &Grid<Option<Object>>,
+    beams: &mut DirectionBitGrid,
+    coordinates: Coordinates,
+    direction: Direction,
+) {
+    if beams.set(&coordinates, &direction) {
+        let directions = match &grid[&coordinates] {
+            None => vec![direction],
+            Some(Object::Mirror(mirror)) => vec![mirror.reflect(&direction)],
+            Some(Object::Splitter(splitter)) => splitter.split(&direction),
+        };
+        for direction in directions {
+            if let Some(coordinates) = coordinates.next(&direction, &grid.size) {
+                advance_beam(grid, beams, coordinates, direction);
             }
         }
     }
+}
 
-    fn energy(&self) -> usize {
-        self.tiles
-            .iter()
-            .map(|row| row.iter().filter(|tile| tile.is_energized()).count())
-            .sum()
-    }
-
-    fn reset(&mut self) {
-        for row in &mut self.tiles {
-            for tile in row {
-                tile.reset();
-            }
-        }
-    }
+fn parse(input: &str) -> Grid<Option<Object>> {
+    Grid::from_iter(input.lines().map(|line| line.chars().map(Object::parse)))
 }
 
 pub fn part_one(input: &str) -> usize {
-    let mut grid = Grid::from(input);
-    grid.advance_beam((0, 0), Direction::Right);
-    grid.energy()
+    let grid = parse(input);
+    let mut beams = DirectionBitGrid::new(&grid.size);
+    advance_beam(
+        &grid,
+        &mut beams,
+        Coordinates { x: 0, y: 0 },
+        Direction::Right,
+    );
+    beams.count()
 }
 
 pub fn part_two(input: &str) -> usize {
-    let mut grid = Grid::from(input);
+    let grid = parse(input);
+    let mut beams = DirectionBitGrid::new(&grid.size);
+    let Coordinates {
+        x: width,
+        y: height,
+    } = grid.size.clone();
+    let starts = (0..height)
+        .flat_map(|y| {
+            [
+                (Coordinates { x: 0, y }, Direction::Right),
+                (Coordinates { x: width - 1, y }, Direction::Left),
+            ]
+        })
+        .chain((0..width).flat_map(|x| {
+            [
+                (Coordinates { x, y: 0 }, Direction::Down),
+                (Coordinates { x, y: height - 1 }, Direction::Up),
+            ]
+        }));
     let mut max_energy = 0;
-    for i in 0..grid.size {
-        grid.advance_beam((0, i), Direction::Right);
-        max_energy = max_energy.max(grid.energy());
-        grid.reset();
-        grid.advance_beam((i, grid.size - 1), Direction::Up);
-        max_energy = max_energy.max(grid.energy());
-        grid.reset();
-        grid.advance_beam((grid.size - 1, i), Direction::Left);
-        max_energy = max_energy.max(grid.energy());
-        grid.reset();
-        grid.advance_beam((i, 0), Direction::Down);
-        max_energy = max_energy.max(grid.energy());
-        grid.reset();
+    for (coordinates, direction) in starts {
+        advance_beam(&grid, &mut beams, coordinates, direction);
+        max_energy = max_energy.max(beams.count());
+        beams.clear();
     }
     max_energy
 }
@@ -1,6 +1,4 @@
-use pathfinding::prelude::dijkstra;
-
-use crate::grid::{Coordinates, Direction, Grid};
+use crate::grid::{Coordinates, Grid, Movement};
 
 fn parse(input: &str) -> Grid<u32> {
     Grid::from_iter(
@@ -10,102 +8,39 @@ fn parse(input: &str) -> Grid<u32> {
     )
 }
 
-pub fn part_one(input: &str) -> u32 {
-    let map = parse(input);
+fn minimal_heat_loss(map: &Grid<u32>, movement: &Movement) -> u32 {
     let start = Coordinates { x: 0, y: 0 };
     let finish = Coordinates {
         x: map.size.x - 1,
         y: map.size.y - 1,
     };
-    dijkstra(
-        &(start, Direction::Right, 0),
-        |(position, current_direction, current_line_len)| {
-            Direction::iter()
-                .filter_map(|direction| {
-                    if direction != &current_direction.reverse()
-                        && (direction != current_direction || current_line_len < &3)
-                    {
-                        position.next(direction, &map.size).map(|position| {
-                            let cost = map[&position];
-                            (
-                                (
-                                    position,
-                                    direction.clone(),
-                                    if direction == current_direction {
-                                        current_line_len + 1
-                                    } else {
-                                        1
-                                    },
-                                ),
-                                cost,
-                            )
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
+    map.shortest_path(start, &finish, movement, |heat_loss| Some(*heat_loss))
+        .unwrap()
+        .1
+}
+
+pub fn part_one(input: &str) -> u32 {
+    minimal_heat_loss(
+        &parse(input),
+        &Movement {
+            min_straight: 0,
+            max_straight: 3,
+            can_turn: true,
+            can_reverse: false,
         },
-        |(position, _, _)| position == &finish,
     )
-    .unwrap()
-    .1
 }
 
 pub fn part_two(input: &str) -> u32 {
-    let map = parse(input);
-    let start = Coordinates { x: 0, y: 0 };
-    let finish = Coordinates {
-        x: map.size.x - 1,
-        y: map.size.y - 1,
-    };
-    dijkstra(
-        &(start, Direction::Right, 0),
-        |(position, current_direction, current_line_len)| {
-            if current_line_len > &0 && current_line_len < &4 {
-                position
-                    .next(current_direction, &map.size)
-                    .map(|position| {
-                        let cost = map[&position];
-                        vec![(
-                            (position, current_direction.clone(), current_line_len + 1),
-                            cost,
-                        )]
-                    })
-                    .unwrap_or(Vec::new())
-            } else {
-                Direction::iter()
-                    .filter_map(|direction| {
-                        if current_line_len == &0
-                            || (direction != &current_direction.reverse()
-                                && (direction != current_direction || current_line_len < &10))
-                        {
-                            position.next(direction, &map.size).map(|position| {
-                                let cost = map[&position];
-                                (
-                                    (
-                                        position,
-                                        direction.clone(),
-                                        if direction == current_direction {
-                                            current_line_len + 1
-                                        } else {
-                                            1
-                                        },
-                                    ),
-                                    cost,
-                                )
-                            })
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            }
+    minimal_heat_loss(
+        &parse(input),
+        &Movement {
+            min_straight: 4,
+            max_straight: 10,
+            can_turn: true,
+            can_reverse: false,
         },
-        |(position, _, current_line_len)| position == &finish && current_line_len >= &4,
     )
-    .unwrap()
-    .1
 }
 
 #[cfg(test)]
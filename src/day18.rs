@@ -1,7 +1,7 @@
 use itertools::Itertools;
 
 use crate::grid::Direction;
-use crate::regex;
+use crate::{parse_caps, regex};
 
 type Instruction = (Direction, usize);
 
@@ -9,12 +9,11 @@ fn points(instructions: &[Instruction]) -> Vec<(i64, i64)> {
     let mut points = vec![(0, 0)];
     for (direction, distance) in instructions {
         let previous_point = points.last().unwrap();
-        points.push(match direction {
-            Direction::Up => (previous_point.0, previous_point.1 - *distance as i64),
-            Direction::Right => (previous_point.0 + *distance as i64, previous_point.1),
-            Direction::Down => (previous_point.0, previous_point.1 + *distance as i64),
-            Direction::Left => (previous_point.0 - *distance as i64, previous_point.1),
-        })
+        let (dx, dy) = direction.delta();
+        points.push((
+            previous_point.0 + dx as i64 * *distance as i64,
+            previous_point.1 + dy as i64 * *distance as i64,
+        ))
     }
     points
 }
@@ -30,44 +29,26 @@ fn inner_area(points: &[(i64, i64)]) -> u64 {
         / 2
 }
 
-// the shoelace formula doesn't take the borders into account
+// the shoelace formula measures the polygon through the middle of the border cells
 fn outer_area(instructions: &[Instruction]) -> u64 {
-    // number of borders = number of corners
-    let number_of_borders = instructions.len() as u64;
-    // there are 4 more outward corners than inward ones because it's a closed polygon
-    let inward_corners = (number_of_borders - 4) / 2;
-    let outward_corners = inward_corners + 4;
     let total_border_len = instructions
         .iter()
         .map(|(_, distance)| distance)
         .sum::<usize>() as u64;
-    // each border section that is not a corner adds 1/2
-    // each outward corner adds 3/4
-    // each inward corner add 1/4
-    (total_border_len - number_of_borders) / 2 + (3 * outward_corners + inward_corners) / 4
+    // Pick's theorem: the border cells add half a cell each, plus one for the closed loop
+    total_border_len / 2 + 1
 }
 
 pub fn part_one(input: &str) -> u64 {
     let instructions = regex!(r"(?P<direction>[URDL]) (?P<distance>\d+) \(#[[:xdigit:]]{6}\)")
         .captures_iter(input)
-        .map(|caps| {
-            (
-                match &caps["direction"] {
-                    "U" => Direction::Up,
-                    "R" => Direction::Right,
-                    "D" => Direction::Down,
-                    "L" => Direction::Left,
-                    _ => unreachable!(),
-                },
-                caps["distance"].parse().unwrap(),
-            )
-        })
+        .map(|caps| parse_caps!(caps, ("direction", "distance")).unwrap())
         .collect::<Vec<Instruction>>();
     inner_area(&points(&instructions)) + outer_area(&instructions)
 }
 
-pub fn part_two(input: &str) -> u64 {
-    let instructions = regex!(r"[URDL] \d+ \(#(?P<distance>[[:xdigit:]]{5})(?P<direction>[0-3])\)")
+fn parse_colors(input: &str) -> Vec<Instruction> {
+    regex!(r"[URDL] \d+ \(#(?P<distance>[[:xdigit:]]{5})(?P<direction>[0-3])\)")
         .captures_iter(input)
         .map(|caps| {
             (
@@ -81,7 +62,11 @@ pub fn part_two(input: &str) -> u64 {
                 usize::from_str_radix(&caps["distance"], 16).unwrap(),
             )
         })
-        .collect::<Vec<Instruction>>();
+        .collect()
+}
+
+pub fn part_two(input: &str) -> u64 {
+    let instructions = parse_colors(input);
     inner_area(&points(&instructions)) + outer_area(&instructions)
 }
 
@@ -90,6 +75,7 @@ mod tests {
     use std::fs::read_to_string;
 
     use super::*;
+    use crate::grid::{Cells, CompressedGrid, Coordinates};
 
     #[test]
     fn test_part_one() {
@@ -102,4 +88,28 @@ mod tests {
         let input = read_to_string("examples/18/1").unwrap();
         assert_eq!(part_two(&input), 952408144115);
     }
+
+    #[test]
+    fn test_compressed_flood_fill() {
+        let input = read_to_string("examples/18/1").unwrap();
+        let instructions = parse_colors(&input);
+        let trench = points(&instructions)
+            .into_iter()
+            .circular_tuple_windows()
+            .map(|(a, b)| {
+                (
+                    a.0.min(b.0)..(a.0.max(b.0) + 1),
+                    a.1.min(b.1)..(a.1.max(b.1) + 1),
+                )
+            })
+            .collect::<Vec<_>>();
+        let map = CompressedGrid::new(&trench, false, true);
+        let outside = map
+            .grid
+            .flood_fill(Coordinates { x: 0, y: 0 }, |_, to, _| !to);
+        assert_eq!(
+            map.total_area() - outside.iter().map(|cell| map.area(cell)).sum::<u64>(),
+            952408144115
+        );
+    }
 }
@@ -1,9 +1,10 @@
 use std::cmp::Ordering;
-use std::collections::HashMap;
 use std::ops::{self, Range};
 use std::str::FromStr;
 
-use crate::regex;
+use crate::interner::Interner;
+use crate::parse;
+use crate::{parse_caps, regex};
 
 struct Part {
     x: usize,
@@ -126,42 +127,40 @@ struct IfRule {
     category: Category,
     compare: Compare,
     compare_to: usize,
-    destination: String,
+    destination: usize,
 }
 
-impl FromStr for IfRule {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl IfRule {
+    fn parse(s: &str, names: &mut Interner) -> Self {
         let caps = regex!(
             r"(?P<category>[xmas])(?P<compare>[<>])(?P<compare_to>\d+):(?P<destination>[a-z]+|[AR])"
         )
         .captures(s)
         .unwrap();
-        Ok(Self {
-            category: caps["category"].parse().unwrap(),
-            compare: caps["compare"].parse().unwrap(),
-            compare_to: caps["compare_to"].parse().unwrap(),
-            destination: caps["destination"].to_owned(),
-        })
+        let (category, compare, compare_to) =
+            parse_caps!(caps, ("category", "compare", "compare_to")).unwrap();
+        Self {
+            category,
+            compare,
+            compare_to,
+            destination: names.intern(&caps["destination"]),
+        }
     }
-}
 
-impl IfRule {
-    fn apply(&self, part: &Part) -> Option<String> {
+    fn apply(&self, part: &Part) -> Option<usize> {
         match (part[&self.category].cmp(&self.compare_to), &self.compare) {
             (Ordering::Less, Compare::Inf) | (Ordering::Greater, Compare::Sup) => {
-                Some(self.destination.clone())
+                Some(self.destination)
             }
             _ => None,
         }
     }
 
-    fn apply_range(&self, part: &mut PartRange) -> Option<(PartRange, String)> {
+    fn apply_range(&self, part: &mut PartRange) -> Option<(PartRange, usize)> {
         if part[&self.category].contains(&self.compare_to) {
             Some((
                 part.split(&self.category, &self.compare, self.compare_to),
-                self.destination.clone(),
+                self.destination,
             ))
         } else {
             None
@@ -171,126 +170,157 @@ impl IfRule {
 
 struct Rules {
     if_rules: Vec<IfRule>,
-    else_rule: String,
+    else_rule: usize,
 }
 
-impl FromStr for Rules {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
+impl Rules {
+    fn parse(s: &str, names: &mut Interner) -> Self {
+        Self {
             if_rules: regex!(r"(?P<if_rule>[xmas][<>]\d+:(?:[a-z]+|[AR])),")
                 .captures_iter(s)
-                .map(|caps| caps["if_rule"].parse().unwrap())
+                .map(|caps| IfRule::parse(&caps["if_rule"], names))
                 .collect(),
-            else_rule: regex!(r"(?P<else_rule>(?:[a-z]+|[AR]))$")
-                .captures(s)
-                .unwrap()["else_rule"]
-                .to_owned(),
-        })
+            else_rule: names.intern(
+                &regex!(r"(?P<else_rule>(?:[a-z]+|[AR]))$")
+                    .captures(s)
+                    .unwrap()["else_rule"],
+            ),
+        }
     }
-}
 
-impl Rules {
-    fn apply(&self, part: &Part) -> String {
+    fn apply(&self, part: &Part) -> usize {
         for if_rule in &self.if_rules {
             if let Some(destination) = if_rule.apply(part) {
                 return destination;
             }
         }
-        self.else_rule.clone()
+        self.else_rule
     }
 
-    fn apply_range(&self, mut part: PartRange) -> Vec<(PartRange, String)> {
+    fn apply_range(&self, mut part: PartRange) -> Vec<(PartRange, usize)> {
         let mut parts = Vec::new();
         for if_rule in &self.if_rules {
             if let Some((part, destination)) = if_rule.apply_range(&mut part) {
                 parts.push((part, destination));
             }
         }
-        parts.push((part, self.else_rule.clone()));
+        parts.push((part, self.else_rule));
         parts
     }
 }
 
-fn parse(input: &str) -> (HashMap<String, Rules>, Vec<Part>) {
-    let caps = regex!(
-        r"(?P<workflows>(?:[a-z]+\{(?:[xmas][<>]\d+:(?:[a-z]+|[AR]),)+(?:[a-z]+|[AR])\}\n)+)\n(?P<parts>(?:\{x=\d+,m=\d+,a=\d+,s=\d+\}\n)+)"
-    ).captures(input).unwrap();
-    (
+/// The rules of every workflow, indexed by the id of its name
+///
+/// The accept and reject destinations have ids too, but no rules
+struct Workflows {
+    names: Interner,
+    rules: Vec<Option<Rules>>,
+    start: usize,
+    accepted: usize,
+    rejected: usize,
+}
+
+fn parse(input: &str) -> (Workflows, Vec<Part>) {
+    let caps = parse::captures(
         regex!(
-            r"(?P<name>[a-z]+)\{(?P<rules>(?:[xmas][<>]\d+:(?:[a-z]+|[AR]),)+(?:[a-z]+|[AR]))\}"
+            r"(?P<workflows>(?:[a-z]+\{(?:[xmas][<>]\d+:(?:[a-z]+|[AR]),)+(?:[a-z]+|[AR])\}\n)+)\n(?P<parts>(?:\{x=\d+,m=\d+,a=\d+,s=\d+\}\n)+)"
+        ),
+        input,
+        "workflows, a blank line, then parts",
+    )
+    .unwrap();
+    let mut names = Interner::new();
+    let accepted = names.intern("A");
+    let rejected = names.intern("R");
+    let start = names.intern("in");
+    let workflows = regex!(
+        r"(?P<name>[a-z]+)\{(?P<rules>(?:[xmas][<>]\d+:(?:[a-z]+|[AR]),)+(?:[a-z]+|[AR]))\}"
+    )
+    .captures_iter(&caps["workflows"])
+    .map(|caps| {
+        (
+            names.intern(&caps["name"]),
+            Rules::parse(&caps["rules"], &mut names),
         )
-        .captures_iter(&caps["workflows"])
-        .map(|caps| (caps["name"].to_owned(), caps["rules"].parse().unwrap()))
-        .collect::<HashMap<String, Rules>>(),
+    })
+    .collect::<Vec<_>>();
+    let mut rules = (0..names.len()).map(|_| None).collect::<Vec<_>>();
+    for (name, workflow) in workflows {
+        rules[name] = Some(workflow);
+    }
+    (
+        Workflows {
+            names,
+            rules,
+            start,
+            accepted,
+            rejected,
+        },
         regex!(r"\{x=(?P<x>\d+),m=(?P<m>\d+),a=(?P<a>\d+),s=(?P<s>\d+)\}")
             .captures_iter(&caps["parts"])
-            .map(|caps| Part {
-                x: caps["x"].parse().unwrap(),
-                m: caps["m"].parse().unwrap(),
-                a: caps["a"].parse().unwrap(),
-                s: caps["s"].parse().unwrap(),
-            })
+            .map(|caps| parse_caps!(caps, Part { x, m, a, s }).unwrap())
             .collect(),
     )
 }
 
-fn process(part: &Part, workflows: &HashMap<String, Rules>) -> bool {
-    let mut current_workflow = "in".to_owned();
-    loop {
-        let rules = &workflows[&current_workflow];
-        let next_workflow = rules.apply(part);
-        match next_workflow.as_str() {
-            "A" => break true,
-            "R" => break false,
-            _ => {
-                current_workflow = next_workflow;
+impl Workflows {
+    fn rules(&self, workflow: usize) -> &Rules {
+        match &self.rules[workflow] {
+            Some(rules) => rules,
+            None => panic!("no workflow named {}", &self.names[workflow]),
+        }
+    }
+
+    fn process(&self, part: &Part) -> bool {
+        let mut current_workflow = self.start;
+        loop {
+            match self.rules(current_workflow).apply(part) {
+                next_workflow if next_workflow == self.accepted => break true,
+                next_workflow if next_workflow == self.rejected => break false,
+                next_workflow => current_workflow = next_workflow,
             }
         }
     }
+
+    fn count_all(&self) -> usize {
+        let mut parts = vec![(
+            PartRange {
+                x: 1..4001,
+                m: 1..4001,
+                a: 1..4001,
+                s: 1..4001,
+            },
+            self.start,
+        )];
+        let mut accepted = 0;
+        while !parts.is_empty() {
+            parts = parts
+                .into_iter()
+                .flat_map(|(part, workflow)| self.rules(workflow).apply_range(part))
+                .filter(|(part, workflow)| {
+                    if *workflow == self.accepted {
+                        accepted += part.count();
+                    }
+                    *workflow != self.accepted && *workflow != self.rejected
+                })
+                .collect();
+        }
+        accepted
+    }
 }
 
 pub fn part_one(input: &str) -> usize {
     let (workflows, parts) = parse(input);
     parts
         .into_iter()
-        .filter(|part| process(part, &workflows))
+        .filter(|part| workflows.process(part))
         .map(|part| part.sum())
         .sum()
 }
 
-fn count_all(workflows: &HashMap<String, Rules>) -> usize {
-    let mut parts = vec![(
-        PartRange {
-            x: 1..4001,
-            m: 1..4001,
-            a: 1..4001,
-            s: 1..4001,
-        },
-        "in".to_owned(),
-    )];
-    let mut accepted = 0;
-    while !parts.is_empty() {
-        parts = parts
-            .into_iter()
-            .flat_map(|(part, workflow)| workflows[&workflow].apply_range(part))
-            .filter(|(part, workflow)| match workflow.as_str() {
-                "A" => {
-                    accepted += part.count();
-                    false
-                }
-                "R" => false,
-                _ => true,
-            })
-            .collect();
-    }
-    accepted
-}
-
 pub fn part_two(input: &str) -> usize {
     let (workflows, _) = parse(input);
-    count_all(&workflows)
+    workflows.count_all()
 }
 
 #[cfg(test)]
@@ -310,4 +340,11 @@ mod tests {
         let input = read_to_string("examples/19/1").unwrap();
         assert_eq!(part_two(&input), 167409079868000);
     }
+
+    #[test]
+    #[should_panic(expected = "line 1, column 16: expected workflows, a blank line, then parts")]
+    fn test_parse_error() {
+        let input = read_to_string("examples/19/1").unwrap();
+        parse(&input.replace("m>2090", "m=2090"));
+    }
 }
@@ -1,132 +1,146 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 
-use crate::cache::hash;
+use crate::interner::Interner;
 use crate::regex;
 
 struct State {
-    modules: HashMap<u64, Module>,
+    names: Interner,
+    // indexed by module id, untyped modules such as rx have none
+    modules: Vec<Option<Module>>,
     low_pulse_count: usize,
     high_pulse_count: usize,
-    before_rx: Option<u64>,
+    button: usize,
+    broadcaster: usize,
+    before_rx: Option<usize>,
     // frequency at which the module before rx receives a high pulse from each of its input modules
-    cycles: HashMap<u64, Option<usize>>,
+    cycles: HashMap<usize, Option<usize>>,
 }
 
 impl FromStr for State {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut modules =
+        let mut names = Interner::new();
+        let button = names.intern("button");
+        let broadcaster = names.intern("broadcaster");
+        let parsed_modules =
             regex!(r"(?P<name>broadcaster|(?:[%&][a-z]+)) -> (?P<outputs>(?:[a-z]+, )*[a-z]+)")
                 .captures_iter(s)
                 .map(|caps| {
+                    let id = names.intern(caps["name"].trim_start_matches(['%', '&']));
                     let outputs = regex!(r"(?P<name>[a-z]+)")
                         .captures_iter(&caps["outputs"])
-                        .map(|caps| hash(&caps["name"]))
+                        .map(|caps| names.intern(&caps["name"]))
                         .collect();
-                    match &caps["name"] {
-                        "broadcaster" => {
-                            let id = crate::cache::hash("broadcaster");
-                            (id, Module::Broadcaster(BroadcasterModule { id, outputs }))
-                        }
-                        name if name.starts_with("%") => {
-                            let id = crate::cache::hash(&name[1..]);
-                            (
-                                id,
-                                Module::FlipFlop(FlipFlopModule {
-                                    id,
-                                    on: false,
-                                    outputs,
-                                }),
-                            )
-                        }
-                        name if name.starts_with("&") => {
-                            let id = crate::cache::hash(&name[1..]);
-                            (
-                                id,
-                                Module::Conjunction(ConjunctionModule {
-                                    id,
-                                    memory: HashMap::new(),
-                                    outputs,
-                                }),
-                            )
-                        }
+                    let module = match &caps["name"] {
+                        "broadcaster" => Module::Broadcaster(BroadcasterModule { id, outputs }),
+                        name if name.starts_with("%") => Module::FlipFlop(FlipFlopModule {
+                            id,
+                            on: false,
+                            outputs,
+                        }),
+                        name if name.starts_with("&") => Module::Conjunction(ConjunctionModule {
+                            id,
+                            memory: HashMap::new(),
+                            outputs,
+                        }),
                         _ => unreachable!(),
-                    }
+                    };
+                    (id, module)
                 })
-                .collect::<HashMap<_, _>>();
+                .collect::<Vec<_>>();
+        let mut modules = (0..names.len()).map(|_| None).collect::<Vec<_>>();
+        for (id, module) in parsed_modules {
+            modules[id] = Some(module);
+        }
         let mut conjunction_inputs = HashMap::new();
-        for (id, module) in &modules {
-            for output in module.outputs() {
-                if let Some(Module::Conjunction(_)) = modules.get(output) {
+        for (id, module) in modules.iter().enumerate() {
+            for output in module.iter().flat_map(|module| module.outputs()) {
+                if let Some(Module::Conjunction(_)) = &modules[*output] {
                     conjunction_inputs
                         .entry(*output)
                         .or_insert(Vec::new())
-                        .push(*id);
+                        .push(id);
                 }
             }
         }
-        for (name, inputs) in conjunction_inputs {
-            if let Module::Conjunction(conjunction) = modules.get_mut(&name).unwrap() {
+        for (id, inputs) in conjunction_inputs {
+            if let Some(Module::Conjunction(conjunction)) = &mut modules[id] {
                 for input in inputs {
                     conjunction.memory.insert(input, false);
                 }
             }
         }
-        let before_rx = modules
-            .iter()
-            .find_map(|(id, module)| {
-                if module.outputs().iter().any(|output| output == &hash("rx")) {
-                    Some(id)
-                } else {
-                    None
-                }
-            })
-            .cloned();
-        let cycle_lens = if let Some(before_rx) = before_rx {
+        let modules_to = |to: usize| {
             modules
-                .keys()
-                .filter_map(|id| {
-                    if modules[id]
-                        .outputs()
-                        .iter()
-                        .any(|output| output == &before_rx)
-                    {
-                        Some((*id, None))
-                    } else {
-                        None
-                    }
+                .iter()
+                .enumerate()
+                .filter(move |(_, module)| {
+                    module
+                        .as_ref()
+                        .is_some_and(|module| module.outputs().contains(&to))
                 })
-                .collect::<HashMap<_, _>>()
+                .map(|(id, _)| id)
+        };
+        let before_rx = names.get("rx").and_then(|rx| modules_to(rx).next());
+        let cycles = if let Some(before_rx) = before_rx {
+            modules_to(before_rx).map(|id| (id, None)).collect()
         } else {
             HashMap::new()
         };
         Ok(Self {
+            names,
             modules,
             low_pulse_count: 0,
             high_pulse_count: 0,
+            button,
+            broadcaster,
             before_rx,
-            cycles: cycle_lens,
+            cycles,
         })
     }
 }
 
+impl fmt::Display for State {
+    /// The configuration of the modules, in the input format
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (id, module) in self.modules.iter().enumerate() {
+            let Some(module) = module else {
+                continue;
+            };
+            let prefix = match module {
+                Module::Broadcaster(_) => "",
+                Module::FlipFlop(_) => "%",
+                Module::Conjunction(_) => "&",
+            };
+            let outputs = module
+                .outputs()
+                .iter()
+                .map(|output| &self.names[*output])
+                .collect::<Vec<_>>();
+            writeln!(f, "{prefix}{} -> {}", &self.names[id], outputs.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
 impl State {
     fn push_button(&mut self, count: usize) {
         self.resolve(
             vec![(
                 Pulse {
-                    from: 0,
+                    from: self.button,
                     high: false,
                 },
-                vec![hash("broadcaster")],
+                vec![self.broadcaster],
             )],
             count,
         );
     }
 
-    fn resolve(&mut self, pulses: Vec<(Pulse, Vec<u64>)>, count: usize) {
+    fn resolve(&mut self, pulses: Vec<(Pulse, Vec<usize>)>, count: usize) {
         let mut next_pulses = Vec::new();
         for (pulse, outputs) in pulses {
             if pulse.high {
@@ -138,7 +152,7 @@ impl State {
                 if self.before_rx.is_some_and(|before_rx| output == before_rx) && pulse.high {
                     *self.cycles.get_mut(&pulse.from).unwrap() = Some(count);
                 }
-                if let Some(module) = self.modules.get_mut(&output) {
+                if let Some(module) = &mut self.modules[output] {
                     if let Some(pulse) = module.resolve(&pulse) {
                         next_pulses.push((pulse, module.outputs().to_vec()));
                     }
@@ -171,7 +185,7 @@ enum Module {
 }
 
 impl Module {
-    fn outputs(&self) -> &[u64] {
+    fn outputs(&self) -> &[usize] {
         match self {
             Self::Broadcaster(broadcaster_module) => &broadcaster_module.outputs,
             Self::FlipFlop(flip_flop_module) => &flip_flop_module.outputs,
@@ -189,8 +203,8 @@ impl Module {
 }
 
 struct BroadcasterModule {
-    id: u64,
-    outputs: Vec<u64>,
+    id: usize,
+    outputs: Vec<usize>,
 }
 
 impl BroadcasterModule {
@@ -203,9 +217,9 @@ impl BroadcasterModule {
 }
 
 struct FlipFlopModule {
-    id: u64,
+    id: usize,
     on: bool,
-    outputs: Vec<u64>,
+    outputs: Vec<usize>,
 }
 
 impl FlipFlopModule {
@@ -223,9 +237,9 @@ impl FlipFlopModule {
 }
 
 struct ConjunctionModule {
-    id: u64,
-    memory: HashMap<u64, bool>,
-    outputs: Vec<u64>,
+    id: usize,
+    memory: HashMap<usize, bool>,
+    outputs: Vec<usize>,
 }
 
 impl ConjunctionModule {
@@ -239,7 +253,7 @@ impl ConjunctionModule {
 }
 
 struct Pulse {
-    from: u64,
+    from: usize,
     high: bool,
 }
 
@@ -267,6 +281,8 @@ pub fn part_two(input: &str) -> usize {
 mod tests {
     use std::fs::read_to_string;
 
+    use itertools::Itertools;
+
     use super::*;
 
     #[test]
@@ -276,4 +292,15 @@ mod tests {
         let input = read_to_string("examples/20/2").unwrap();
         assert_eq!(part_one(&input), 11687500);
     }
+
+    #[test]
+    fn test_display() {
+        let input = read_to_string("examples/20/2").unwrap();
+        let display = input.parse::<State>().unwrap().to_string();
+        // modules are listed in the order of their ids
+        assert_eq!(
+            display.lines().sorted().collect::<Vec<_>>(),
+            input.lines().sorted().collect::<Vec<_>>()
+        );
+    }
 }
@@ -1,8 +1,15 @@
-use std::{iter, ops, slice};
+use std::collections::{hash_map, HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::hash::Hash;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fs, io, iter, ops, slice};
 
-use itertools::Itertools;
+use itertools::{iproduct, Itertools};
+use num::Zero;
+use pathfinding::prelude::dijkstra;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Coordinates {
     pub x: usize,
     pub y: usize,
@@ -13,6 +20,235 @@ pub struct Grid<C> {
     pub size: Coordinates,
 }
 
+/// A grid that only stores the cells that differ from a default value
+///
+/// Useful when the map is mostly empty or too large to be materialised as a [`Grid`]. The
+/// coordinates are 2-D unless `P` says otherwise, as for [`crate::grid3::SparseGrid3`].
+pub struct SparseGrid<C, P = Coordinates> {
+    cells: HashMap<P, C>,
+    default: C,
+    /// Inclusive corners of the smallest box containing every stored cell
+    bounds: Option<(P, P)>,
+}
+
+/// Coordinates with any number of axes, as far as a [`SparseGrid`] is concerned
+pub trait Point: Clone + Default + Eq + Hash {
+    /// Combine the coordinates of `self` and `other` axis by axis
+    fn zip_with(&self, other: &Self, function: impl Fn(usize, usize) -> usize) -> Self;
+
+    /// Whether `self` and `other` have the same coordinate on some axis
+    fn shares_axis(&self, other: &Self) -> bool;
+}
+
+/// Axis-aligned rectangle of cells
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub origin: Coordinates,
+    pub size: Coordinates,
+}
+
+/// Borrowed rectangular part of a [`Grid`], indexed with coordinates relative to its origin
+pub struct GridView<'a, C> {
+    grid: &'a Grid<C>,
+    pub rect: Rect,
+}
+
+/// One bit per cell, for visited sets that are cheap to test and to reset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    pub size: Coordinates,
+}
+
+/// One bit per cell and [`Direction`], for tracking which headings already went through a cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectionBitGrid {
+    /// four consecutive bits per cell, in the order of [`Direction::iter`]
+    bits: BitGrid,
+}
+
+/// A [`Grid`] over a huge plane where each cell stands for a rectangle of real cells
+///
+/// Built from rectangles so that each of them covers whole cells, everything in between being
+/// merged together
+pub struct CompressedGrid<C> {
+    pub grid: Grid<C>,
+    /// Real coordinate where each column starts, followed by the end of the last one
+    xs: Vec<i64>,
+    /// Real coordinate where each row starts, followed by the end of the last one
+    ys: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+/// Read access shared by [`Grid`], [`SparseGrid`] and [`GridView`], so that helpers can work on all of them
+pub trait Cells {
+    type Cell;
+
+    /// Exclusive upper bound of the coordinates, used to stop at the edges
+    fn size(&self) -> Coordinates;
+
+    fn get(&self, coordinates: &Coordinates) -> Option<&Self::Cell>;
+
+    /// Every cell of a [`Grid`], or every stored cell of a [`SparseGrid`]
+    fn cells(&self) -> impl Iterator<Item = (Coordinates, &Self::Cell)>;
+
+    fn neighbors_of(
+        &self,
+        coordinates: &Coordinates,
+    ) -> impl Iterator<Item = (Coordinates, Direction, &Self::Cell)> {
+        let size = self.size();
+        Direction::iter().filter_map(move |direction| {
+            let position = coordinates.next(direction, &size)?;
+            self.get(&position)
+                .map(|cell| (position, direction.clone(), cell))
+        })
+    }
+
+    fn positions<'a>(
+        &'a self,
+        mut predicate: impl FnMut(&Self::Cell) -> bool + 'a,
+    ) -> impl Iterator<Item = Coordinates> + 'a {
+        self.cells()
+            .filter_map(move |(position, cell)| predicate(cell).then_some(position))
+    }
+
+    fn position(&self, predicate: impl FnMut(&Self::Cell) -> bool) -> Option<Coordinates> {
+        self.positions(predicate).next()
+    }
+
+    /// Every cell reachable from `start`, including itself
+    #[allow(dead_code)]
+    fn flood_fill(
+        &self,
+        start: Coordinates,
+        mut passable: impl FnMut(&Self::Cell, &Self::Cell, &Direction) -> bool,
+    ) -> HashSet<Coordinates> {
+        let mut region = HashSet::from([start.clone()]);
+        let mut stack = vec![start];
+        while let Some(position) = stack.pop() {
+            let Some(cell) = self.get(&position) else {
+                continue;
+            };
+            for (neighbor, direction, neighbor_cell) in self.neighbors_of(&position) {
+                if !region.contains(&neighbor) && passable(cell, neighbor_cell, &direction) {
+                    region.insert(neighbor.clone());
+                    stack.push(neighbor);
+                }
+            }
+        }
+        region
+    }
+}
+
+/// [`Cells`] that hold every cell up to their size, for the helpers that label each of them
+///
+/// A [`SparseGrid`] may stand for a plane far too large for that, so it only gets the helpers of
+/// [`Cells`], whose cost depends on the cells they reach.
+pub trait DenseCells: Cells {
+    /// Breadth-first distance from the closest source to every reachable cell
+    ///
+    /// `passable(from, to, direction)` tells whether the step from a cell to its neighbor is allowed
+    #[allow(dead_code)]
+    fn distances(
+        &self,
+        sources: impl IntoIterator<Item = Coordinates>,
+        mut passable: impl FnMut(&Self::Cell, &Self::Cell, &Direction) -> bool,
+    ) -> Grid<Option<usize>> {
+        let mut distances = Grid::new(&self.size(), None);
+        let mut queue = VecDeque::new();
+        for source in sources {
+            if distances[&source].is_none() {
+                distances[&source] = Some(0);
+                queue.push_back(source);
+            }
+        }
+        while let Some(position) = queue.pop_front() {
+            let distance = distances[&position].map(|distance| distance + 1);
+            let Some(cell) = self.get(&position) else {
+                continue;
+            };
+            for (neighbor, direction, neighbor_cell) in self.neighbors_of(&position) {
+                if distances[&neighbor].is_none() && passable(cell, neighbor_cell, &direction) {
+                    distances[&neighbor] = distance;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Label every cell with the index of its region, two neighbors being in the same region
+    /// if `connected(from, to, direction)` holds
+    #[allow(dead_code)]
+    fn components(
+        &self,
+        mut connected: impl FnMut(&Self::Cell, &Self::Cell, &Direction) -> bool,
+    ) -> (Grid<usize>, Vec<Region>) {
+        let size = self.size();
+        let mut labels = Grid::new(&size, None);
+        let mut regions = Vec::new();
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let start = Coordinates { x, y };
+                if labels[&start].is_some() {
+                    continue;
+                }
+                let label = regions.len();
+                let mut area = 0;
+                labels[&start] = Some(label);
+                let mut stack = vec![start];
+                while let Some(position) = stack.pop() {
+                    area += 1;
+                    let Some(cell) = self.get(&position) else {
+                        continue;
+                    };
+                    for (neighbor, direction, neighbor_cell) in self.neighbors_of(&position) {
+                        if labels[&neighbor].is_none() && connected(cell, neighbor_cell, &direction)
+                        {
+                            labels[&neighbor] = Some(label);
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+                regions.push(Region { area, perimeter: 0 });
+            }
+        }
+        let labels = labels.map(|label| label.unwrap());
+        for (position, label) in &labels {
+            let same_region_neighbors = position
+                .neighbors(&size)
+                .filter(|(neighbor, _)| &labels[neighbor] == label)
+                .count();
+            regions[*label].perimeter += 4 - same_region_neighbors;
+        }
+        (labels, regions)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub area: usize,
+    /// Number of cell sides bordering another region or the edge of the grid
+    pub perimeter: usize,
+}
+
+/// Movement rules for [`Grid::shortest_path`]
+#[derive(Debug, Clone)]
+pub struct Movement {
+    /// Steps to take in a straight line before being allowed to turn or stop
+    pub min_straight: usize,
+    /// Steps after which turning is mandatory
+    pub max_straight: usize,
+    pub can_turn: bool,
+    pub can_reverse: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Direction {
     Up,
@@ -21,7 +257,40 @@ pub enum Direction {
     Left,
 }
 
+/// Change of heading relative to the current one
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Turn {
+    Straight,
+    Right,
+    Back,
+    Left,
+}
+
 impl Coordinates {
+    /// Move `steps` cells towards `direction`, unless that leaves the grid
+    pub fn offset(&self, direction: &Direction, steps: usize, size: &Coordinates) -> Option<Self> {
+        let (x, y) = match direction {
+            Direction::Up => (Some(self.x), self.y.checked_sub(steps)),
+            Direction::Right => (self.x.checked_add(steps), Some(self.y)),
+            Direction::Down => (Some(self.x), self.y.checked_add(steps)),
+            Direction::Left => (self.x.checked_sub(steps), Some(self.y)),
+        };
+        let position = Self { x: x?, y: y? };
+        (position.x < size.x && position.y < size.y).then_some(position)
+    }
+
+    /// `self - other`, unless `other` is further than `self` on an axis
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_sub(other.x)?,
+            y: self.y.checked_sub(other.y)?,
+        })
+    }
+
+    pub fn manhattan(&self, other: &Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
     pub fn next(&self, direction: &Direction, size: &Coordinates) -> Option<Self> {
         match direction {
             Direction::Up => {
@@ -35,7 +304,7 @@ impl Coordinates {
                 }
             }
             Direction::Right => {
-                if self.x < size.x - 1 {
+                if self.x + 1 < size.x {
                     Some(Self {
                         x: self.x + 1,
                         y: self.y,
@@ -45,7 +314,7 @@ impl Coordinates {
                 }
             }
             Direction::Down => {
-                if self.y < size.y - 1 {
+                if self.y + 1 < size.y {
                     Some(Self {
                         x: self.x,
                         y: self.y + 1,
@@ -77,6 +346,17 @@ impl Coordinates {
         })
     }
 
+    /// The up to 8 positions around, diagonals included
+    pub fn surrounding<'a>(&'a self, size: &'a Coordinates) -> impl Iterator<Item = Self> + 'a {
+        iproduct!(-1isize..=1, -1isize..=1)
+            .filter(|delta| delta != &(0, 0))
+            .filter_map(|(dx, dy)| {
+                let x = self.x.checked_add_signed(dx)?;
+                let y = self.y.checked_add_signed(dy)?;
+                (x < size.x && y < size.y).then_some(Self { x, y })
+            })
+    }
+
     pub fn all_neighbors<'a>(
         positions: impl IntoIterator<Item = &'a Self> + 'a,
         size: &'a Coordinates,
@@ -87,6 +367,28 @@ impl Coordinates {
     }
 }
 
+impl ops::Add for Coordinates {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+        }
+    }
+}
+
+/// Panics, in release builds too, if `rhs` is further than `self` on an axis, see
+/// [`Coordinates::checked_sub`] otherwise
+impl ops::Sub for Coordinates {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(&rhs)
+            .unwrap_or_else(|| panic!("{rhs:?} is further than {self:?} on an axis"))
+    }
+}
+
 impl<C, A: IntoIterator<Item = C>> FromIterator<A> for Grid<C> {
     fn from_iter<T: IntoIterator<Item = A>>(iter: T) -> Self {
         let cells: Vec<Vec<C>> = iter.into_iter().map(|i| i.into_iter().collect()).collect();
@@ -127,9 +429,712 @@ impl<'a, C> IntoIterator for &'a Grid<C> {
     }
 }
 
+/// Rendering, mostly useful to look at a puzzle while debugging
 impl<C> Grid<C> {
+    fn pixels(&self, mut color: impl FnMut(&C) -> Color) -> Vec<Vec<u8>> {
+        self.cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .flat_map(|cell| {
+                        let Color { r, g, b } = color(cell);
+                        [r, g, b]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Binary PPM image with one pixel per cell
+    pub fn to_ppm(&self, color: impl FnMut(&C) -> Color) -> Vec<u8> {
+        let mut ppm = format!("P6\n{} {}\n255\n", self.size.x, self.size.y).into_bytes();
+        ppm.extend(self.pixels(color).into_iter().flatten());
+        ppm
+    }
+
+    /// PNG image with one pixel per cell, stored without compression
+    pub fn to_png(&self, color: impl FnMut(&C) -> Color) -> Vec<u8> {
+        fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+            png.extend((data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend(kind);
+            png.extend(data);
+            let crc = crc32(&png[start..]);
+            png.extend(crc.to_be_bytes());
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.size.x as u32).to_be_bytes());
+        header.extend((self.size.y as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filtering and no interlacing
+        header.extend([8, 2, 0, 0, 0]);
+        // each scanline starts with its filter type, 0 is none
+        let scanlines = self
+            .pixels(color)
+            .into_iter()
+            .flat_map(|row| iter::once(0).chain(row))
+            .collect::<Vec<u8>>();
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Write a `.ppm` or `.png` file depending on the extension of `path`
+    pub fn save_image(
+        &self,
+        path: impl AsRef<Path>,
+        color: impl FnMut(&C) -> Color,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let image = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("png") => self.to_png(color),
+            _ => self.to_ppm(color),
+        };
+        fs::write(path, image)
+    }
+
+    /// Two colored terminal columns per cell, with `()` drawn on the highlighted cells
+    pub fn to_ansi(
+        &self,
+        mut color: impl FnMut(&C) -> Color,
+        highlighted: &HashSet<Coordinates>,
+    ) -> String {
+        let mut output = String::new();
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let Color { r, g, b } = color(cell);
+                let content = if highlighted.contains(&Coordinates { x, y }) {
+                    // black or white depending on the luminance of the background
+                    let foreground = if 299 * r as u32 + 587 * g as u32 + 114 * b as u32 > 127_500 {
+                        0
+                    } else {
+                        255
+                    };
+                    format!("\x1b[38;2;{foreground};{foreground};{foreground}m()")
+                } else {
+                    "  ".to_owned()
+                };
+                write!(output, "\x1b[48;2;{r};{g};{b}m{content}").unwrap();
+            }
+            output.push_str("\x1b[0m\n");
+        }
+        output
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    static TABLE: once_cell::sync::Lazy<[u32; 256]> = once_cell::sync::Lazy::new(|| {
+        let mut table = [0; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            *entry = (0..8).fold(n as u32, |c, _| {
+                if c & 1 == 1 {
+                    0xedb88320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                }
+            });
+        }
+        table
+    });
+    !data.iter().fold(!0, |crc, byte| {
+        TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        stream.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        stream.push(is_final as u8);
+        let len = block.len() as u16;
+        stream.extend(len.to_le_bytes());
+        stream.extend((!len).to_le_bytes());
+        stream.extend(block);
+    }
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    stream.extend(((b << 16) | a).to_be_bytes());
+    stream
+}
+
+impl<C: Clone> Grid<C> {
+    pub fn new(size: &Coordinates, value: C) -> Self {
+        Grid::from_iter(iter::repeat_n(iter::repeat_n(value, size.x), size.y))
+    }
+
+    /// Surround the grid with `width` cells of `value` on every side
+    pub fn pad(&self, width: usize, value: C) -> Self {
+        let offset = Coordinates { x: width, y: width };
+        let mut padded = Grid::new(
+            &(self.size.clone() + offset.clone() + offset.clone()),
+            value,
+        );
+        for (position, cell) in self {
+            padded[&(position + offset.clone())] = cell.clone();
+        }
+        padded
+    }
+
+    pub fn crop(&self, rect: Rect) -> Self {
+        Grid::from_iter(self.view(rect).rows().map(|row| row.iter().cloned()))
+    }
+
+    /// Repeat the grid `x` times horizontally and `y` times vertically
+    pub fn tile(&self, x: usize, y: usize) -> Self {
+        Grid::from_iter(
+            iter::repeat_n(&self.cells, y)
+                .flatten()
+                .map(|row| iter::repeat_n(row, x).flatten().cloned()),
+        )
+    }
+}
+
+impl<C> Grid<C> {
+    pub fn map<T>(self, mut function: impl FnMut(C) -> T) -> Grid<T> {
+        Grid {
+            cells: self
+                .cells
+                .into_iter()
+                .map(|row| row.into_iter().map(&mut function).collect())
+                .collect(),
+            size: self.size,
+        }
+    }
+
+    pub fn map_with_coords<T>(self, mut function: impl FnMut(&Coordinates, C) -> T) -> Grid<T> {
+        Grid {
+            cells: self
+                .cells
+                .into_iter()
+                .enumerate()
+                .map(|(y, row)| {
+                    row.into_iter()
+                        .enumerate()
+                        .map(|(x, cell)| function(&Coordinates { x, y }, cell))
+                        .collect()
+                })
+                .collect(),
+            size: self.size,
+        }
+    }
+
+    pub fn get(&self, coordinates: &Coordinates) -> Option<&C> {
+        self.cells
+            .get(coordinates.y)
+            .and_then(|row| row.get(coordinates.x))
+    }
+
+    /// Cheapest path from `start` to `end` that follows the movement rules, along with its cost
+    ///
+    /// `cost` gives the cost of entering a cell, or `None` if it can't be entered
+    pub fn shortest_path<K: Zero + Ord + Copy>(
+        &self,
+        start: Coordinates,
+        end: &Coordinates,
+        movement: &Movement,
+        mut cost: impl FnMut(&C) -> Option<K>,
+    ) -> Option<(Vec<Coordinates>, K)> {
+        // position, heading and number of steps taken in a straight line
+        let start: (Coordinates, Option<Direction>, usize) = (start, None, 0);
+        dijkstra(
+            &start,
+            |(position, heading, straight)| {
+                Direction::iter()
+                    .filter(|direction| match heading {
+                        None => true,
+                        Some(heading) if direction == &heading => straight < &movement.max_straight,
+                        Some(heading) => {
+                            straight >= &movement.min_straight
+                                && if direction == &&heading.reverse() {
+                                    movement.can_reverse
+                                } else {
+                                    movement.can_turn
+                                }
+                        }
+                    })
+                    .filter_map(|direction| {
+                        let next = position.next(direction, &self.size)?;
+                        let cost = cost(&self[&next])?;
+                        let straight = if heading.as_ref() == Some(direction) {
+                            straight + 1
+                        } else {
+                            1
+                        };
+                        Some(((next, Some(direction.clone()), straight), cost))
+                    })
+                    .collect::<Vec<_>>()
+            },
+            |(position, _, straight)| position == end && straight >= &movement.min_straight,
+        )
+        .map(|(path, cost)| {
+            (
+                path.into_iter().map(|(position, _, _)| position).collect(),
+                cost,
+            )
+        })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[C]> + Clone {
+        self.cells.iter().map(|row| row.as_slice())
+    }
+
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [C]> {
+        self.cells.iter_mut().map(|row| row.as_mut_slice())
+    }
+
+    /// Each column as an iterator over its cells from top to bottom
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &C> + Clone> + Clone {
+        (0..self.size.x).map(move |x| self.cells.iter().map(move |row| &row[x]))
+    }
+
+    pub fn view(&self, rect: Rect) -> GridView<'_, C> {
+        assert!(
+            rect.origin.x + rect.size.x <= self.size.x
+                && rect.origin.y + rect.size.y <= self.size.y
+        );
+        GridView { grid: self, rect }
+    }
+
+    /// Every `size` × `size` view of the grid, row by row
+    pub fn windows(&self, size: usize) -> impl Iterator<Item = GridView<'_, C>> {
+        let positions = |length: usize| 0..(length + 1).saturating_sub(size);
+        positions(self.size.y)
+            .cartesian_product(positions(self.size.x))
+            .map(move |(y, x)| {
+                self.view(Rect {
+                    origin: Coordinates { x, y },
+                    size: Coordinates { x: size, y: size },
+                })
+            })
+    }
+
     pub fn clone_with<T: Clone>(&self, value: T) -> Grid<T> {
-        Grid::from_iter(iter::repeat(iter::repeat(value).take(self.size.x)).take(self.size.y))
+        Grid::from_iter(iter::repeat_n(
+            iter::repeat_n(value, self.size.x),
+            self.size.y,
+        ))
+    }
+}
+
+impl<C> Cells for Grid<C> {
+    type Cell = C;
+
+    fn size(&self) -> Coordinates {
+        self.size.clone()
+    }
+
+    fn get(&self, coordinates: &Coordinates) -> Option<&C> {
+        Grid::get(self, coordinates)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (Coordinates, &C)> {
+        self.into_iter()
+    }
+}
+
+impl<C> DenseCells for Grid<C> {}
+
+impl<'a, C> GridView<'a, C> {
+    pub fn to_global(&self, coordinates: &Coordinates) -> Coordinates {
+        Coordinates {
+            x: self.rect.origin.x + coordinates.x,
+            y: self.rect.origin.y + coordinates.y,
+        }
+    }
+
+    pub fn to_local(&self, coordinates: &Coordinates) -> Option<Coordinates> {
+        let x = coordinates.x.checked_sub(self.rect.origin.x)?;
+        let y = coordinates.y.checked_sub(self.rect.origin.y)?;
+        (x < self.rect.size.x && y < self.rect.size.y).then_some(Coordinates { x, y })
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &'a [C]> + Clone {
+        let columns = self.rect.origin.x..(self.rect.origin.x + self.rect.size.x);
+        self.grid.cells[self.rect.origin.y..(self.rect.origin.y + self.rect.size.y)]
+            .iter()
+            .map(move |row| &row[columns.clone()])
+    }
+}
+
+impl<C> ops::Index<&Coordinates> for GridView<'_, C> {
+    type Output = C;
+
+    fn index(&self, index: &Coordinates) -> &Self::Output {
+        debug_assert!(index.x < self.rect.size.x && index.y < self.rect.size.y);
+        &self.grid[&self.to_global(index)]
+    }
+}
+
+impl<C> Cells for GridView<'_, C> {
+    type Cell = C;
+
+    fn size(&self) -> Coordinates {
+        self.rect.size.clone()
+    }
+
+    fn get(&self, coordinates: &Coordinates) -> Option<&C> {
+        if coordinates.x < self.rect.size.x && coordinates.y < self.rect.size.y {
+            Some(&self[coordinates])
+        } else {
+            None
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (Coordinates, &C)> {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, cell)| (Coordinates { x, y }, cell))
+        })
+    }
+}
+
+impl<C> DenseCells for GridView<'_, C> {}
+
+impl<C: Clone> CompressedGrid<C> {
+    /// Cells covered by one of the half-open `rects` are `inside`, the others are `outside`
+    ///
+    /// There is always a margin of `outside` cells around the rectangles, so that the whole
+    /// outside can be flood-filled from the origin
+    #[allow(dead_code)]
+    pub fn new(rects: &[(ops::Range<i64>, ops::Range<i64>)], outside: C, inside: C) -> Self {
+        fn boundaries(ranges: impl Iterator<Item = ops::Range<i64>>) -> Vec<i64> {
+            let mut boundaries = ranges
+                .flat_map(|range| [range.start, range.end])
+                .sorted()
+                .dedup()
+                .collect::<Vec<_>>();
+            if let (Some(&first), Some(&last)) = (boundaries.first(), boundaries.last()) {
+                boundaries.insert(0, first - 1);
+                boundaries.push(last + 1);
+            }
+            boundaries
+        }
+
+        let xs = boundaries(rects.iter().map(|(xs, _)| xs.clone()));
+        let ys = boundaries(rects.iter().map(|(_, ys)| ys.clone()));
+        let mut grid = Grid::new(
+            &Coordinates {
+                x: xs.len().saturating_sub(1),
+                y: ys.len().saturating_sub(1),
+            },
+            outside,
+        );
+        for (rect_xs, rect_ys) in rects {
+            let columns =
+                xs.binary_search(&rect_xs.start).unwrap()..xs.binary_search(&rect_xs.end).unwrap();
+            let rows =
+                ys.binary_search(&rect_ys.start).unwrap()..ys.binary_search(&rect_ys.end).unwrap();
+            for (y, x) in rows.cartesian_product(columns) {
+                grid[&Coordinates { x, y }] = inside.clone();
+            }
+        }
+        Self { grid, xs, ys }
+    }
+}
+
+impl<C> CompressedGrid<C> {
+    /// Number of real columns merged in a column
+    pub fn width(&self, x: usize) -> u64 {
+        (self.xs[x + 1] - self.xs[x]) as u64
+    }
+
+    /// Number of real rows merged in a row
+    pub fn height(&self, y: usize) -> u64 {
+        (self.ys[y + 1] - self.ys[y]) as u64
+    }
+
+    /// Number of real cells merged in a cell
+    #[allow(dead_code)]
+    pub fn area(&self, coordinates: &Coordinates) -> u64 {
+        self.width(coordinates.x) * self.height(coordinates.y)
+    }
+
+    #[allow(dead_code)]
+    pub fn total_area(&self) -> u64 {
+        (0..self.grid.size.x).map(|x| self.width(x)).sum::<u64>()
+            * (0..self.grid.size.y).map(|y| self.height(y)).sum::<u64>()
+    }
+
+    /// Real coordinates of the top left corner of a cell
+    #[allow(dead_code)]
+    pub fn to_real(&self, coordinates: &Coordinates) -> (i64, i64) {
+        (self.xs[coordinates.x], self.ys[coordinates.y])
+    }
+
+    /// The cell that contains a real point, if it is inside the grid
+    #[allow(dead_code)]
+    pub fn to_compressed(&self, (x, y): (i64, i64)) -> Option<Coordinates> {
+        fn find(boundaries: &[i64], value: i64) -> Option<usize> {
+            match boundaries.binary_search(&value) {
+                Ok(index) => (index + 1 < boundaries.len()).then_some(index),
+                Err(index) => (index > 0 && index < boundaries.len()).then_some(index - 1),
+            }
+        }
+
+        Some(Coordinates {
+            x: find(&self.xs, x)?,
+            y: find(&self.ys, y)?,
+        })
+    }
+}
+
+impl BitGrid {
+    pub fn new(size: &Coordinates) -> Self {
+        Self {
+            words: vec![0; (size.x * size.y).div_ceil(64)],
+            size: size.clone(),
+        }
+    }
+
+    fn bit(&self, coordinates: &Coordinates) -> (usize, u64) {
+        debug_assert!(coordinates.x < self.size.x && coordinates.y < self.size.y);
+        let index = coordinates.y * self.size.x + coordinates.x;
+        (index / 64, 1 << (index % 64))
+    }
+
+    /// Set the bit of a cell, returning whether it was unset before
+    pub fn set(&mut self, coordinates: &Coordinates) -> bool {
+        let (word, mask) = self.bit(coordinates);
+        let was_unset = self.words[word] & mask == 0;
+        self.words[word] |= mask;
+        was_unset
+    }
+
+    pub fn test(&self, coordinates: &Coordinates) -> bool {
+        let (word, mask) = self.bit(coordinates);
+        self.words[word] & mask != 0
+    }
+
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    pub fn union_with(&mut self, other: &Self) {
+        debug_assert_eq!(self.size, other.size);
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    pub fn intersect_with(&mut self, other: &Self) {
+        debug_assert_eq!(self.size, other.size);
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word &= other;
+        }
+    }
+}
+
+impl DirectionBitGrid {
+    pub fn new(size: &Coordinates) -> Self {
+        Self {
+            bits: BitGrid::new(&Coordinates {
+                x: 4 * size.x,
+                y: size.y,
+            }),
+        }
+    }
+
+    fn bit(coordinates: &Coordinates, direction: &Direction) -> Coordinates {
+        Coordinates {
+            x: 4 * coordinates.x + direction.clone() as usize,
+            y: coordinates.y,
+        }
+    }
+
+    /// Set the bit of a cell and direction, returning whether it was unset before
+    pub fn set(&mut self, coordinates: &Coordinates, direction: &Direction) -> bool {
+        self.bits.set(&Self::bit(coordinates, direction))
+    }
+
+    #[allow(dead_code)]
+    pub fn test(&self, coordinates: &Coordinates, direction: &Direction) -> bool {
+        self.bits.test(&Self::bit(coordinates, direction))
+    }
+
+    /// Number of cells with at least one direction set
+    pub fn count(&self) -> usize {
+        // rows are a multiple of 4 bits long, so a cell never straddles two words
+        self.bits
+            .words
+            .iter()
+            .map(|word| {
+                let any = word | word >> 1 | word >> 2 | word >> 3;
+                (any & 0x1111_1111_1111_1111).count_ones() as usize
+            })
+            .sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.clear();
+    }
+
+    #[allow(dead_code)]
+    pub fn union_with(&mut self, other: &Self) {
+        self.bits.union_with(&other.bits);
+    }
+
+    #[allow(dead_code)]
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.bits.intersect_with(&other.bits);
+    }
+}
+
+impl Point for Coordinates {
+    fn zip_with(&self, other: &Self, function: impl Fn(usize, usize) -> usize) -> Self {
+        Self {
+            x: function(self.x, other.x),
+            y: function(self.y, other.y),
+        }
+    }
+
+    fn shares_axis(&self, other: &Self) -> bool {
+        self.x == other.x || self.y == other.y
+    }
+}
+
+impl<C: PartialEq, P: Point> SparseGrid<C, P> {
+    pub fn new(default: C) -> Self {
+        Self {
+            cells: HashMap::new(),
+            default,
+            bounds: None,
+        }
+    }
+
+    /// Store a cell, or forget it if it holds the default value
+    pub fn insert(&mut self, coordinates: P, value: C) -> Option<C> {
+        if value == self.default {
+            return self.remove(&coordinates);
+        }
+        self.bounds = Some(extend_bounds(self.bounds.take(), &coordinates));
+        self.cells.insert(coordinates, value)
+    }
+}
+
+impl<C: PartialEq> SparseGrid<C> {
+    pub fn from_grid(grid: Grid<C>, default: C) -> Self {
+        let mut sparse = Self::new(default);
+        for (y, row) in grid.cells.into_iter().enumerate() {
+            for (x, cell) in row.into_iter().enumerate() {
+                sparse.insert(Coordinates { x, y }, cell);
+            }
+        }
+        sparse
+    }
+}
+
+impl<C, P: Point> SparseGrid<C, P> {
+    pub fn remove(&mut self, coordinates: &P) -> Option<C> {
+        let removed = self.cells.remove(coordinates);
+        if let Some((min, max)) = &self.bounds {
+            // only recompute the bounds if the removed cell was on the surface of the box
+            if removed.is_some() && (coordinates.shares_axis(min) || coordinates.shares_axis(max)) {
+                self.bounds = self.cells.keys().fold(None, |bounds, coordinates| {
+                    Some(extend_bounds(bounds, coordinates))
+                });
+            }
+        }
+        removed
+    }
+
+    /// Inclusive corners of the smallest box containing every stored cell
+    #[allow(dead_code)]
+    pub fn bounds(&self) -> Option<&(P, P)> {
+        self.bounds.as_ref()
+    }
+
+    /// Exclusive far corner of the bounding box, or the origin if the grid is empty
+    ///
+    /// Neighbors are looked for up to there, so that they stay between the origin and the stored
+    /// cells.
+    pub fn far_corner(&self) -> P {
+        self.bounds
+            .as_ref()
+            .map_or_else(P::default, |(_, max)| max.zip_with(max, |axis, _| axis + 1))
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<'_, P, C> {
+        self.cells.iter()
+    }
+}
+
+impl<C: Clone> SparseGrid<C> {
+    /// Materialise the grid from the origin to the far corner of the bounding box
+    #[allow(dead_code)]
+    pub fn to_grid(&self) -> Grid<C> {
+        let size = self.far_corner();
+        Grid::from_iter(
+            (0..size.y).map(|y| (0..size.x).map(move |x| self[&Coordinates { x, y }].clone())),
+        )
+    }
+}
+
+impl<C, P: Point> ops::Index<&P> for SparseGrid<C, P> {
+    type Output = C;
+
+    fn index(&self, index: &P) -> &Self::Output {
+        self.cells.get(index).unwrap_or(&self.default)
+    }
+}
+
+fn extend_bounds<P: Point>(bounds: Option<(P, P)>, coordinates: &P) -> (P, P) {
+    match bounds {
+        Some((min, max)) => (
+            min.zip_with(coordinates, usize::min),
+            max.zip_with(coordinates, usize::max),
+        ),
+        None => (coordinates.clone(), coordinates.clone()),
+    }
+}
+
+impl<C> Cells for SparseGrid<C> {
+    type Cell = C;
+
+    /// See [`SparseGrid::far_corner`], there are no cells at all in an empty grid
+    fn size(&self) -> Coordinates {
+        self.far_corner()
+    }
+
+    fn get(&self, coordinates: &Coordinates) -> Option<&C> {
+        let size = self.size();
+        if coordinates.x < size.x && coordinates.y < size.y {
+            Some(&self[coordinates])
+        } else {
+            None
+        }
+    }
+
+    fn cells(&self) -> impl Iterator<Item = (Coordinates, &C)> {
+        self.cells
+            .iter()
+            .map(|(coordinates, cell)| (coordinates.clone(), cell))
     }
 }
 
@@ -152,4 +1157,388 @@ impl Direction {
             Direction::Left => Direction::Right,
         }
     }
+
+    pub fn turn_right(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn turn_left(&self) -> Self {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Right => Direction::Up,
+            Direction::Down => Direction::Right,
+            Direction::Left => Direction::Down,
+        }
+    }
+
+    pub fn turn(&self, turn: &Turn) -> Self {
+        match turn {
+            Turn::Straight => self.clone(),
+            Turn::Right => self.turn_right(),
+            Turn::Back => self.reverse(),
+            Turn::Left => self.turn_left(),
+        }
+    }
+
+    /// The turn that leads from `self` to `other`
+    pub fn turn_to(&self, other: &Self) -> Turn {
+        if other == self {
+            Turn::Straight
+        } else if other == &self.turn_right() {
+            Turn::Right
+        } else if other == &self.reverse() {
+            Turn::Back
+        } else {
+            Turn::Left
+        }
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self == &Direction::Up || self == &Direction::Down
+    }
+
+    /// Unit vector, with `y` growing downwards like in [`Coordinates`]
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        }
+    }
+}
+
+impl TryFrom<char> for Direction {
+    type Error = ();
+
+    /// Accepts `URDL`, `NESW` and `^>v<`
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            'U' | 'N' | '^' => Ok(Direction::Up),
+            'R' | 'E' | '>' => Ok(Direction::Right),
+            'D' | 'S' | 'v' => Ok(Direction::Down),
+            'L' | 'W' | '<' => Ok(Direction::Left),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars().exactly_one().map_err(|_| ())?.try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_grid() {
+        let grid = Grid::from_iter(["..#.", "....", ".#.."].map(|row| row.chars()));
+        let mut sparse = SparseGrid::from_grid(grid, '.');
+        assert_eq!(sparse.len(), 2);
+        assert_eq!(
+            sparse.bounds(),
+            Some(&(Coordinates { x: 1, y: 0 }, Coordinates { x: 2, y: 2 }))
+        );
+        assert_eq!(sparse.size(), Coordinates { x: 3, y: 3 });
+        assert_eq!(sparse.positions(|cell| cell == &'#').count(), 2);
+        assert_eq!(
+            sparse
+                .neighbors_of(&Coordinates { x: 1, y: 1 })
+                .filter(|(_, _, cell)| cell == &&'#')
+                .count(),
+            1
+        );
+
+        sparse.remove(&Coordinates { x: 2, y: 0 });
+        assert_eq!(
+            sparse.bounds(),
+            Some(&(Coordinates { x: 1, y: 2 }, Coordinates { x: 1, y: 2 }))
+        );
+        let dense = sparse.to_grid();
+        assert_eq!(dense.size, Coordinates { x: 2, y: 3 });
+        assert_eq!(dense[&Coordinates { x: 1, y: 2 }], '#');
+        assert_eq!(dense[&Coordinates { x: 0, y: 0 }], '.');
+
+        let empty = SparseGrid::new('.');
+        let origin = Coordinates { x: 0, y: 0 };
+        assert_eq!(empty.size(), origin);
+        assert_eq!(empty.neighbors_of(&origin).count(), 0);
+        assert_eq!(
+            empty.flood_fill(origin.clone(), |_, _, _| true),
+            HashSet::from([origin])
+        );
+    }
+
+    #[test]
+    fn test_traversal() {
+        let grid = Grid::from_iter(["AAB", "ABB", "CCB"].map(|row| row.chars()));
+        let same = |from: &char, to: &char, _: &Direction| from == to;
+
+        let distances = grid.distances([Coordinates { x: 2, y: 0 }], same);
+        assert_eq!(distances[&Coordinates { x: 2, y: 2 }], Some(2));
+        assert_eq!(distances[&Coordinates { x: 1, y: 1 }], Some(2));
+        assert_eq!(distances[&Coordinates { x: 0, y: 0 }], None);
+
+        let region = grid.flood_fill(Coordinates { x: 0, y: 0 }, same);
+        assert_eq!(region.len(), 3);
+
+        assert_eq!(
+            Coordinates { x: 1, y: 1 }.surrounding(&grid.size).count(),
+            8
+        );
+        assert_eq!(
+            Coordinates { x: 0, y: 2 }
+                .surrounding(&grid.size)
+                .collect::<Vec<_>>(),
+            [
+                Coordinates { x: 0, y: 1 },
+                Coordinates { x: 1, y: 1 },
+                Coordinates { x: 1, y: 2 }
+            ]
+        );
+
+        let (labels, regions) = grid.components(same);
+        assert_eq!(labels[&Coordinates { x: 1, y: 0 }], 0);
+        assert_eq!(labels[&Coordinates { x: 1, y: 2 }], 2);
+        assert_eq!(
+            regions,
+            vec![
+                Region {
+                    area: 3,
+                    perimeter: 8
+                },
+                Region {
+                    area: 4,
+                    perimeter: 10
+                },
+                Region {
+                    area: 2,
+                    perimeter: 6
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let grid = Grid::from_iter(["119", "919", "111"].map(|row| row.chars()));
+        let movement = Movement {
+            min_straight: 0,
+            max_straight: 2,
+            can_turn: true,
+            can_reverse: false,
+        };
+        let (path, cost) = grid
+            .shortest_path(
+                Coordinates { x: 0, y: 0 },
+                &Coordinates { x: 2, y: 2 },
+                &movement,
+                |cell| cell.to_digit(10),
+            )
+            .unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(path.len(), 5);
+        assert_eq!(path.last(), Some(&Coordinates { x: 2, y: 2 }));
+
+        let walled = |cell: &char| (cell != &'9').then_some(1);
+        let movement = Movement {
+            max_straight: 1,
+            ..movement
+        };
+        assert!(grid
+            .shortest_path(
+                Coordinates { x: 0, y: 0 },
+                &Coordinates { x: 2, y: 2 },
+                &movement,
+                walled,
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_views() {
+        let grid = Grid::from_iter(["abc", "def", "ghi"].map(|row| row.chars()));
+        assert_eq!(
+            grid.columns()
+                .map(|column| column.collect::<String>())
+                .collect::<Vec<_>>(),
+            ["adg", "beh", "cfi"]
+        );
+
+        let view = grid.view(Rect {
+            origin: Coordinates { x: 1, y: 1 },
+            size: Coordinates { x: 2, y: 2 },
+        });
+        assert_eq!(view[&Coordinates { x: 0, y: 1 }], 'h');
+        assert_eq!(
+            view.to_global(&Coordinates { x: 1, y: 0 }),
+            Coordinates { x: 2, y: 1 }
+        );
+        assert_eq!(view.to_local(&Coordinates { x: 0, y: 1 }), None);
+        assert_eq!(view.rows().collect::<Vec<_>>(), [['e', 'f'], ['h', 'i']]);
+
+        let windows = grid.windows(2).collect::<Vec<_>>();
+        assert_eq!(windows.len(), 4);
+        assert_eq!(windows[1][&Coordinates { x: 0, y: 0 }], 'b');
+        assert_eq!(
+            windows[3].position(|cell| cell == &'i'),
+            Some(Coordinates { x: 1, y: 1 })
+        );
+    }
+
+    #[test]
+    fn test_images() {
+        let grid = Grid::from_iter([[false, true], [true, true]]);
+        let color = |cell: &bool| {
+            if *cell {
+                Color { r: 255, g: 0, b: 0 }
+            } else {
+                Color { r: 0, g: 0, b: 0 }
+            }
+        };
+        let ppm = grid.to_ppm(color);
+        assert!(ppm.starts_with(b"P6\n2 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 2 * 2 * 3);
+
+        let png = grid.to_png(color);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"));
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+
+        let ansi = grid.to_ansi(color, &HashSet::from([Coordinates { x: 1, y: 0 }]));
+        assert_eq!(ansi.lines().count(), 2);
+        assert!(ansi.starts_with("\x1b[48;2;0;0;0m  \x1b[48;2;255;0;0m\x1b[38;2;255;255;255m()"));
+    }
+
+    #[test]
+    fn test_directions() {
+        assert_eq!("N".parse(), Ok(Direction::Up));
+        assert_eq!(Direction::try_from('<'), Ok(Direction::Left));
+        assert_eq!("UR".parse::<Direction>(), Err(()));
+        for direction in Direction::iter() {
+            for turn in [Turn::Straight, Turn::Right, Turn::Back, Turn::Left] {
+                assert_eq!(direction.turn_to(&direction.turn(&turn)), turn);
+            }
+            assert_eq!(direction.turn_left().turn_left(), direction.reverse());
+        }
+
+        let size = Coordinates { x: 10, y: 10 };
+        let position = Coordinates { x: 2, y: 3 };
+        assert_eq!(
+            position.offset(&Direction::Right, 7, &size),
+            Some(Coordinates { x: 9, y: 3 })
+        );
+        assert_eq!(position.offset(&Direction::Right, 8, &size), None);
+        assert_eq!(position.offset(&Direction::Up, 4, &size), None);
+        assert_eq!(position.manhattan(&Coordinates { x: 5, y: 1 }), 5);
+        assert_eq!(
+            position.clone() + Coordinates { x: 1, y: 1 } - Coordinates { x: 3, y: 0 },
+            Coordinates { x: 0, y: 4 }
+        );
+        assert_eq!(position.checked_sub(&Coordinates { x: 3, y: 0 }), None);
+        assert_eq!(
+            position.checked_sub(&Coordinates { x: 2, y: 1 }),
+            Some(Coordinates { x: 0, y: 2 })
+        );
+    }
+
+    #[test]
+    fn test_bit_grids() {
+        let size = Coordinates { x: 9, y: 8 };
+        let mut a = BitGrid::new(&size);
+        assert!(a.set(&Coordinates { x: 8, y: 7 }));
+        assert!(!a.set(&Coordinates { x: 8, y: 7 }));
+        a.set(&Coordinates { x: 3, y: 1 });
+        let mut b = BitGrid::new(&size);
+        b.set(&Coordinates { x: 3, y: 1 });
+        b.set(&Coordinates { x: 0, y: 0 });
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert_eq!(union.count(), 3);
+        a.intersect_with(&b);
+        assert_eq!(a.count(), 1);
+        assert!(a.test(&Coordinates { x: 3, y: 1 }));
+        assert!(!a.test(&Coordinates { x: 8, y: 7 }));
+        a.clear();
+        assert_eq!(a.count(), 0);
+
+        let mut beams = DirectionBitGrid::new(&size);
+        assert!(beams.set(&Coordinates { x: 8, y: 7 }, &Direction::Left));
+        assert!(beams.set(&Coordinates { x: 8, y: 7 }, &Direction::Up));
+        assert!(beams.set(&Coordinates { x: 2, y: 5 }, &Direction::Right));
+        assert!(!beams.set(&Coordinates { x: 2, y: 5 }, &Direction::Right));
+        assert!(beams.test(&Coordinates { x: 8, y: 7 }, &Direction::Up));
+        assert!(!beams.test(&Coordinates { x: 8, y: 7 }, &Direction::Down));
+        assert_eq!(beams.count(), 2);
+    }
+
+    #[test]
+    fn test_compressed_grid() {
+        // a hollow 1000 × 1000 square with a 1 wide border
+        let rects = [
+            (0..1000, 0..1),
+            (0..1000, 999..1000),
+            (0..1, 0..1000),
+            (999..1000, 0..1000),
+        ];
+        let map = CompressedGrid::new(&rects, false, true);
+        assert_eq!(map.grid.size, Coordinates { x: 5, y: 5 });
+        assert_eq!(map.total_area(), 1002 * 1002);
+        assert_eq!(
+            map.to_compressed((500, 500)),
+            Some(Coordinates { x: 2, y: 2 })
+        );
+        assert_eq!(
+            map.to_compressed((1000, 0)),
+            Some(Coordinates { x: 4, y: 1 })
+        );
+        assert_eq!(map.to_compressed((1001, 0)), None);
+        assert_eq!(map.to_real(&Coordinates { x: 2, y: 1 }), (1, 0));
+        assert_eq!(map.area(&Coordinates { x: 2, y: 2 }), 998 * 998);
+
+        let outside = map
+            .grid
+            .flood_fill(Coordinates { x: 0, y: 0 }, |_, to, _| !to);
+        let outside_area = outside.iter().map(|cell| map.area(cell)).sum::<u64>();
+        assert_eq!(map.total_area() - outside_area, 1000 * 1000);
+    }
+
+    #[test]
+    fn test_transformations() {
+        let grid = Grid::from_iter(["ab", "cd"].map(|row| row.chars()));
+        let render = |grid: &Grid<char>| {
+            grid.rows()
+                .map(|row| row.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(render(&grid.pad(1, '#')), ["####", "#ab#", "#cd#", "####"]);
+        assert_eq!(
+            render(&grid.tile(3, 2)),
+            ["ababab", "cdcdcd", "ababab", "cdcdcd"]
+        );
+        assert_eq!(
+            render(&grid.tile(3, 2).crop(Rect {
+                origin: Coordinates { x: 1, y: 1 },
+                size: Coordinates { x: 4, y: 2 },
+            })),
+            ["dcdc", "baba"]
+        );
+        let codes =
+            grid.map_with_coords(|position, cell| format!("{cell}{}", position.x + 2 * position.y));
+        assert_eq!(codes[&Coordinates { x: 1, y: 1 }], "d3");
+        assert_eq!(
+            codes.map(|code| code.len()).size,
+            Coordinates { x: 2, y: 2 }
+        );
+    }
 }
@@ -0,0 +1,344 @@
+use std::{iter, ops, slice};
+
+use itertools::iproduct;
+
+use crate::grid::{Coordinates, Grid, Point, Rect, SparseGrid};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Coordinates3 {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(dead_code)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Direction3 {
+    NegativeX,
+    PositiveX,
+    NegativeY,
+    PositiveY,
+    NegativeZ,
+    PositiveZ,
+}
+
+/// Axis-aligned box, both corners included
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cuboid {
+    pub min: Coordinates3,
+    pub max: Coordinates3,
+}
+
+pub struct Grid3<C> {
+    cells: Vec<C>,
+    pub size: Coordinates3,
+}
+
+pub type SparseGrid3<C> = SparseGrid<C, Coordinates3>;
+
+impl Coordinates3 {
+    #[allow(dead_code)]
+    pub fn axis(&self, axis: Axis) -> usize {
+        match axis {
+            Axis::X => self.x,
+            Axis::Y => self.y,
+            Axis::Z => self.z,
+        }
+    }
+
+    pub fn next(&self, direction: &Direction3, size: &Coordinates3) -> Option<Self> {
+        let Self { x, y, z } = self.clone();
+        match direction {
+            Direction3::NegativeX => x.checked_sub(1).map(|x| Self { x, y, z }),
+            Direction3::PositiveX => (x + 1 < size.x).then_some(Self { x: x + 1, y, z }),
+            Direction3::NegativeY => y.checked_sub(1).map(|y| Self { x, y, z }),
+            Direction3::PositiveY => (y + 1 < size.y).then_some(Self { x, y: y + 1, z }),
+            Direction3::NegativeZ => z.checked_sub(1).map(|z| Self { x, y, z }),
+            Direction3::PositiveZ => (z + 1 < size.z).then_some(Self { x, y, z: z + 1 }),
+        }
+    }
+
+    pub fn neighbors<'a>(
+        &'a self,
+        size: &'a Coordinates3,
+    ) -> impl Iterator<Item = (Self, Direction3)> + 'a {
+        Direction3::iter().filter_map(|direction| {
+            self.next(direction, size)
+                .map(|position| (position, direction.clone()))
+        })
+    }
+
+    /// Drop one axis, the remaining ones keep their order (`Y` gives `(x, z)`)
+    pub fn project(&self, axis: Axis) -> Coordinates {
+        match axis {
+            Axis::X => Coordinates {
+                x: self.y,
+                y: self.z,
+            },
+            Axis::Y => Coordinates {
+                x: self.x,
+                y: self.z,
+            },
+            Axis::Z => Coordinates {
+                x: self.x,
+                y: self.y,
+            },
+        }
+    }
+}
+
+impl Direction3 {
+    pub fn iter() -> slice::Iter<'static, Self> {
+        static DIRECTIONS: [Direction3; 6] = [
+            Direction3::NegativeX,
+            Direction3::PositiveX,
+            Direction3::NegativeY,
+            Direction3::PositiveY,
+            Direction3::NegativeZ,
+            Direction3::PositiveZ,
+        ];
+        DIRECTIONS.iter()
+    }
+}
+
+impl Cuboid {
+    #[allow(dead_code)]
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let min = Coordinates3 {
+            x: self.min.x.max(other.min.x),
+            y: self.min.y.max(other.min.y),
+            z: self.min.z.max(other.min.z),
+        };
+        let max = Coordinates3 {
+            x: self.max.x.min(other.max.x),
+            y: self.max.y.min(other.max.y),
+            z: self.max.z.min(other.max.z),
+        };
+        (min.x <= max.x && min.y <= max.y && min.z <= max.z).then_some(Self { min, max })
+    }
+
+    pub fn size(&self) -> Coordinates3 {
+        Coordinates3 {
+            x: self.max.x - self.min.x + 1,
+            y: self.max.y - self.min.y + 1,
+            z: self.max.z - self.min.z + 1,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn volume(&self) -> usize {
+        let size = self.size();
+        size.x * size.y * size.z
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Coordinates3> {
+        iproduct!(
+            self.min.z..=self.max.z,
+            self.min.y..=self.max.y,
+            self.min.x..=self.max.x
+        )
+        .map(|(z, y, x)| Coordinates3 { x, y, z })
+    }
+
+    /// Shadow of the box on the plane orthogonal to `axis`
+    #[allow(dead_code)]
+    pub fn project(&self, axis: Axis) -> Rect {
+        let min = self.min.project(axis);
+        let max = self.max.project(axis);
+        Rect {
+            size: Coordinates {
+                x: max.x - min.x + 1,
+                y: max.y - min.y + 1,
+            },
+            origin: min,
+        }
+    }
+}
+
+impl<C: Clone> Grid3<C> {
+    #[allow(dead_code)]
+    pub fn new(size: Coordinates3, value: C) -> Self {
+        Self {
+            cells: iter::repeat_n(value, size.x * size.y * size.z).collect(),
+            size,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn fill(&mut self, cuboid: &Cuboid, value: C) {
+        for position in cuboid.points() {
+            self[&position] = value.clone();
+        }
+    }
+}
+
+impl<C> Grid3<C> {
+    fn offset(&self, coordinates: &Coordinates3) -> usize {
+        debug_assert!(
+            coordinates.x < self.size.x
+                && coordinates.y < self.size.y
+                && coordinates.z < self.size.z
+        );
+        coordinates.x + self.size.x * (coordinates.y + self.size.y * coordinates.z)
+    }
+
+    #[allow(dead_code)]
+    pub fn get(&self, coordinates: &Coordinates3) -> Option<&C> {
+        if coordinates.x < self.size.x && coordinates.y < self.size.y && coordinates.z < self.size.z
+        {
+            Some(&self.cells[self.offset(coordinates)])
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Coordinates3, &C)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(offset, cell)| (from_offset(&self.size, offset), cell))
+    }
+
+    #[allow(dead_code)]
+    pub fn neighbors_of<'a>(
+        &'a self,
+        coordinates: &'a Coordinates3,
+    ) -> impl Iterator<Item = (Coordinates3, Direction3, &'a C)> + 'a {
+        coordinates
+            .neighbors(&self.size)
+            .map(|(position, direction)| {
+                let cell = &self[&position];
+                (position, direction, cell)
+            })
+    }
+
+    /// Flatten the grid along `axis`, folding each line of cells parallel to it
+    #[allow(dead_code)]
+    pub fn project<T: Clone>(
+        &self,
+        axis: Axis,
+        init: T,
+        mut fold: impl FnMut(T, &C) -> T,
+    ) -> Grid<T> {
+        let mut projection = Grid::new(&self.size.project(axis), init);
+        for (position, cell) in self.iter() {
+            let projected = position.project(axis);
+            projection[&projected] = fold(projection[&projected].clone(), cell);
+        }
+        projection
+    }
+}
+
+/// Inverse of [`Grid3::offset`]
+fn from_offset(size: &Coordinates3, offset: usize) -> Coordinates3 {
+    Coordinates3 {
+        x: offset % size.x,
+        y: offset / size.x % size.y,
+        z: offset / (size.x * size.y),
+    }
+}
+
+impl<C> ops::Index<&Coordinates3> for Grid3<C> {
+    type Output = C;
+
+    fn index(&self, index: &Coordinates3) -> &Self::Output {
+        &self.cells[self.offset(index)]
+    }
+}
+
+impl<C> ops::IndexMut<&Coordinates3> for Grid3<C> {
+    fn index_mut(&mut self, index: &Coordinates3) -> &mut Self::Output {
+        let offset = self.offset(index);
+        &mut self.cells[offset]
+    }
+}
+
+impl Point for Coordinates3 {
+    fn zip_with(&self, other: &Self, function: impl Fn(usize, usize) -> usize) -> Self {
+        Self {
+            x: function(self.x, other.x),
+            y: function(self.y, other.y),
+            z: function(self.z, other.z),
+        }
+    }
+
+    fn shares_axis(&self, other: &Self) -> bool {
+        self.x == other.x || self.y == other.y || self.z == other.z
+    }
+}
+
+impl<C> SparseGrid3<C> {
+    /// Neighbors in all 6 directions, up to [`SparseGrid::far_corner`] as in 2-D
+    #[allow(dead_code)]
+    pub fn neighbors_of<'a>(
+        &'a self,
+        coordinates: &'a Coordinates3,
+    ) -> impl Iterator<Item = (Coordinates3, Direction3, &'a C)> + 'a {
+        let size = self.far_corner();
+        Direction3::iter().filter_map(move |direction| {
+            let position = coordinates.next(direction, &size)?;
+            let cell = &self[&position];
+            Some((position, direction.clone(), cell))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid3() {
+        let mut grid = Grid3::new(Coordinates3 { x: 3, y: 2, z: 4 }, 0);
+        let brick = Cuboid {
+            min: Coordinates3 { x: 0, y: 1, z: 1 },
+            max: Coordinates3 { x: 2, y: 1, z: 2 },
+        };
+        grid.fill(&brick, 1);
+        assert_eq!(brick.volume(), 6);
+        assert_eq!(grid.iter().filter(|(_, cell)| **cell == 1).count(), 6);
+        assert_eq!(
+            grid.neighbors_of(&Coordinates3 { x: 1, y: 0, z: 1 })
+                .count(),
+            5
+        );
+
+        let heights = grid.project(Axis::Z, 0, |height, cell| height + cell);
+        assert_eq!(heights.size, Coordinates { x: 3, y: 2 });
+        assert_eq!(heights[&Coordinates { x: 1, y: 1 }], 2);
+        assert_eq!(heights[&Coordinates { x: 1, y: 0 }], 0);
+        assert_eq!(
+            brick.project(Axis::Z),
+            Rect {
+                origin: Coordinates { x: 0, y: 1 },
+                size: Coordinates { x: 3, y: 1 },
+            }
+        );
+
+        let mut sparse = SparseGrid3::new(0);
+        for (position, cell) in grid.iter() {
+            sparse.insert(position, *cell);
+        }
+        assert_eq!(
+            sparse.bounds(),
+            Some(&(brick.min.clone(), brick.max.clone()))
+        );
+        sparse.remove(&Coordinates3 { x: 2, y: 1, z: 2 });
+        sparse.remove(&Coordinates3 { x: 2, y: 1, z: 1 });
+        assert_eq!(sparse.bounds().unwrap().1.x, 1);
+        assert_eq!(sparse.far_corner(), Coordinates3 { x: 2, y: 2, z: 3 });
+        assert_eq!(
+            sparse
+                .neighbors_of(&Coordinates3 { x: 1, y: 1, z: 2 })
+                .count(),
+            3
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt;
+
+/// Something `normalize` changed in an input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    StrippedBom,
+    ConvertedLineEndings(usize),
+    TrimmedLines(usize),
+    RemovedTrailingBlankLines(usize),
+    AddedFinalNewline,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StrippedBom => write!(f, "stripped the byte order mark"),
+            Self::ConvertedLineEndings(count) => {
+                write!(f, "converted {count} CRLF or CR line endings to LF")
+            }
+            Self::TrimmedLines(count) => {
+                write!(f, "trimmed trailing whitespace from {count} lines")
+            }
+            Self::RemovedTrailingBlankLines(count) => {
+                write!(f, "removed {count} blank lines at the end")
+            }
+            Self::AddedFinalNewline => write!(f, "added the final newline"),
+        }
+    }
+}
+
+/// Bring an input to the shape the solutions expect: no byte order mark, LF line endings and a
+/// final newline
+///
+/// With `trim`, trailing whitespace is also removed from every line, and blank lines from the
+/// end of the input.
+pub fn normalize(input: &str, trim: bool) -> (String, Vec<Change>) {
+    let mut changes = Vec::new();
+    let input = match input.strip_prefix('\u{feff}') {
+        Some(stripped) => {
+            changes.push(Change::StrippedBom);
+            stripped
+        }
+        None => input,
+    };
+    // A lone `\r` is an old Mac line ending, it counts as one like `\r\n`
+    let converted_count = input.matches('\r').count();
+    let mut normalized = if converted_count > 0 {
+        changes.push(Change::ConvertedLineEndings(converted_count));
+        input.replace("\r\n", "\n").replace('\r', "\n")
+    } else {
+        input.to_owned()
+    };
+    if trim {
+        let mut trimmed_count = 0;
+        let mut lines = normalized
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_end();
+                if trimmed.len() < line.len() {
+                    trimmed_count += 1;
+                }
+                trimmed
+            })
+            .collect::<Vec<_>>();
+        let line_count = lines.len();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        if trimmed_count > 0 {
+            changes.push(Change::TrimmedLines(trimmed_count));
+        }
+        if lines.len() < line_count {
+            changes.push(Change::RemovedTrailingBlankLines(line_count - lines.len()));
+        }
+        let mut trimmed = lines.join("\n");
+        if !lines.is_empty() && (normalized.ends_with('\n') || lines.len() < line_count) {
+            trimmed.push('\n');
+        }
+        normalized = trimmed;
+    }
+    if !normalized.is_empty() && !normalized.ends_with('\n') {
+        changes.push(Change::AddedFinalNewline);
+        normalized.push('\n');
+    }
+    (normalized, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("1abc2\n", false), ("1abc2\n".to_owned(), vec![]));
+        assert_eq!(normalize("", true), (String::new(), vec![]));
+        assert_eq!(
+            normalize("\n\n", true),
+            (String::new(), vec![Change::RemovedTrailingBlankLines(2)])
+        );
+        assert_eq!(
+            normalize("a\r\r\n", false),
+            ("a\n\n".to_owned(), vec![Change::ConvertedLineEndings(2)])
+        );
+        assert_eq!(
+            normalize("a\r\r\n", true),
+            (
+                "a\n".to_owned(),
+                vec![
+                    Change::ConvertedLineEndings(2),
+                    Change::RemovedTrailingBlankLines(1)
+                ]
+            )
+        );
+        assert_eq!(
+            normalize("\u{feff}1abc2\r\npqr3stu8vwx\r\n", false),
+            (
+                "1abc2\npqr3stu8vwx\n".to_owned(),
+                vec![Change::StrippedBom, Change::ConvertedLineEndings(2)]
+            )
+        );
+        assert_eq!(
+            normalize("Time: 7 15  \nDistance: 9 40", false),
+            (
+                "Time: 7 15  \nDistance: 9 40\n".to_owned(),
+                vec![Change::AddedFinalNewline]
+            )
+        );
+
+        let (normalized, changes) = normalize("Time: 7 15  \r\nDistance: 9 40\r\n\r\n \r\n", true);
+        assert_eq!(normalized, "Time: 7 15\nDistance: 9 40\n");
+        assert_eq!(
+            changes,
+            vec![
+                Change::ConvertedLineEndings(4),
+                Change::TrimmedLines(2),
+                Change::RemovedTrailingBlankLines(2)
+            ]
+        );
+        assert_eq!(
+            changes.iter().map(Change::to_string).collect::<Vec<_>>(),
+            [
+                "converted 4 CRLF or CR line endings to LF",
+                "trimmed trailing whitespace from 2 lines",
+                "removed 2 blank lines at the end"
+            ]
+        );
+    }
+}
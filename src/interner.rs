@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::ops;
+
+/// Two-way mapping between names and dense ids, so that named things can live in `Vec`s
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the id of `name`, giving it the next free one if it is new
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        id
+    }
+
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Names in the order of their ids
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+}
+
+impl ops::Index<usize> for Interner {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.name(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interner() {
+        let mut names = Interner::new();
+        assert!(names.is_empty());
+        assert_eq!(names.intern("AAA"), 0);
+        assert_eq!(names.intern("BBB"), 1);
+        assert_eq!(names.intern("AAA"), 0);
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get("BBB"), Some(1));
+        assert_eq!(names.get("ZZZ"), None);
+        assert_eq!(&names[1], "BBB");
+        assert_eq!(names.names().collect::<Vec<_>>(), ["AAA", "BBB"]);
+    }
+}
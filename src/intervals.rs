@@ -0,0 +1,297 @@
+use std::ops;
+
+pub type Range = ops::Range<u64>;
+
+fn shifted(range: &Range, offset: i64) -> Range {
+    range.start.wrapping_add_signed(offset)..range.end.wrapping_add_signed(offset)
+}
+
+fn intersection(a: &Range, b: &Range) -> Option<Range> {
+    let range = a.start.max(b.start)..a.end.min(b.end);
+    (!range.is_empty()).then_some(range)
+}
+
+/// Set of integers stored as sorted ranges, which are never empty, overlapping or adjacent
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    ranges: Vec<Range>,
+}
+
+impl FromIterator<Range> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Range>>(iter: T) -> Self {
+        let mut ranges = iter
+            .into_iter()
+            .filter(|range| !range.is_empty())
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<Range> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Self { ranges: merged }
+    }
+}
+
+impl IntervalSet {
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(dead_code)]
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of integers in the set
+    pub fn len(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        self.ranges.first().map(|range| range.start)
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, value: u64) -> bool {
+        let index = self.ranges.partition_point(|range| range.end <= value);
+        self.ranges
+            .get(index)
+            .is_some_and(|range| range.contains(&value))
+    }
+
+    #[allow(dead_code)]
+    pub fn union(&self, other: &Self) -> Self {
+        self.ranges.iter().chain(&other.ranges).cloned().collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (&self.ranges[i], &other.ranges[j]);
+            ranges.extend(intersection(a, b));
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    #[allow(dead_code)]
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            while j < other.ranges.len() && other.ranges[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < range.end {
+                if other.ranges[k].start > start {
+                    ranges.push(start..other.ranges[k].start);
+                }
+                start = start.max(other.ranges[k].end);
+                k += 1;
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+}
+
+/// Map that adds an offset to the values of each of its source ranges, and leaves the other
+/// values unchanged
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    /// Sorted, disjoint, and without zero offsets
+    pieces: Vec<(Range, i64)>,
+}
+
+impl FromIterator<(Range, i64)> for PiecewiseMap {
+    /// The source ranges must not overlap
+    fn from_iter<T: IntoIterator<Item = (Range, i64)>>(iter: T) -> Self {
+        let mut pieces = iter
+            .into_iter()
+            .filter(|(range, offset)| !range.is_empty() && *offset != 0)
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|(range, _)| range.start);
+        assert!(
+            pieces
+                .windows(2)
+                .all(|pair| pair[0].0.end <= pair[1].0.start),
+            "overlapping source ranges"
+        );
+        // merge adjacent pieces that shift by the same offset
+        let mut merged: Vec<(Range, i64)> = Vec::with_capacity(pieces.len());
+        for (range, offset) in pieces {
+            match merged.last_mut() {
+                Some((last, last_offset)) if last.end == range.start && *last_offset == offset => {
+                    last.end = range.end
+                }
+                _ => merged.push((range, offset)),
+            }
+        }
+        Self { pieces: merged }
+    }
+}
+
+impl PiecewiseMap {
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    fn offset_at(&self, value: u64) -> i64 {
+        let index = self.pieces.partition_point(|(range, _)| range.end <= value);
+        match self.pieces.get(index) {
+            Some((range, offset)) if range.contains(&value) => *offset,
+            _ => 0,
+        }
+    }
+
+    pub fn apply(&self, value: u64) -> u64 {
+        value.wrapping_add_signed(self.offset_at(value))
+    }
+
+    /// Pieces and the gaps between them, covering every value
+    fn segments(&self) -> Vec<(Range, i64)> {
+        let mut segments = Vec::with_capacity(2 * self.pieces.len() + 1);
+        let mut start = 0;
+        for (range, offset) in &self.pieces {
+            if start < range.start {
+                segments.push((start..range.start, 0));
+            }
+            segments.push((range.clone(), *offset));
+            start = range.end;
+        }
+        if start < u64::MAX {
+            segments.push((start..u64::MAX, 0));
+        }
+        segments
+    }
+
+    pub fn apply_set(&self, set: &IntervalSet) -> IntervalSet {
+        self.segments()
+            .into_iter()
+            .flat_map(|(source, offset)| {
+                set.intersection(&IntervalSet::from_iter([source]))
+                    .ranges
+                    .into_iter()
+                    .map(move |range| shifted(&range, offset))
+            })
+            .collect()
+    }
+
+    /// The map that applies `self`, then `other`
+    pub fn then(&self, other: &Self) -> Self {
+        let other_segments = other.segments();
+        self.segments()
+            .into_iter()
+            .flat_map(|(source, offset)| {
+                let image = shifted(&source, offset);
+                other_segments
+                    .iter()
+                    .filter_map(move |(other_source, other_offset)| {
+                        let overlap = intersection(&image, other_source)?;
+                        Some((shifted(&overlap, -offset), offset + other_offset))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// The map that undoes `self`, if it is a bijection
+    #[allow(dead_code)]
+    pub fn inverse(&self) -> Option<Self> {
+        let sources = self.pieces.iter().map(|(range, _)| range.clone());
+        let images = self
+            .pieces
+            .iter()
+            .map(|(range, offset)| shifted(range, *offset))
+            .collect::<Vec<_>>();
+        let image_set = images.iter().cloned().collect::<IntervalSet>();
+        // the images must not overlap, and exactly fill the values taken from the identity
+        let is_bijection = image_set.len()
+            == images.iter().map(|range| range.end - range.start).sum()
+            && image_set == sources.collect();
+        is_bijection.then(|| {
+            images
+                .into_iter()
+                .zip(&self.pieces)
+                .map(|(image, (_, offset))| (image, -offset))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_sets() {
+        let a = IntervalSet::from_iter([5..8, 0..2, 2..3, 7..10, 12..12]);
+        assert_eq!(a.ranges(), [0..3, 5..10]);
+        assert_eq!(a.len(), 8);
+        assert!(a.contains(9) && !a.contains(3) && !a.contains(10));
+
+        let b = IntervalSet::from_iter([1..6, 9..20]);
+        let union = a.union(&b);
+        assert_eq!(
+            (union.ranges().len(), union.min(), union.len()),
+            (1, Some(0), 20)
+        );
+        assert_eq!(a.intersection(&b).ranges(), [1..3, 5..6, 9..10]);
+        assert_eq!(a.difference(&b).ranges(), [0..1, 6..9]);
+        assert_eq!(b.difference(&a).ranges(), [3..5, 10..20]);
+        assert!(a.difference(&a).is_empty());
+        assert_eq!(IntervalSet::new().min(), None);
+    }
+
+    #[test]
+    fn test_piecewise_maps() {
+        // seed-to-soil map of the day 5 example
+        let map = PiecewiseMap::from_iter([(98..100, -48), (50..98, 2)]);
+        assert_eq!(
+            [79, 14, 55, 13, 98].map(|seed| map.apply(seed)),
+            [81, 14, 57, 13, 50]
+        );
+        assert_eq!(
+            map.apply_set(&IntervalSet::from_iter([45..52, 97..99]))
+                .ranges(),
+            [45..50, 50..51, 52..54, 99..100]
+                .into_iter()
+                .collect::<IntervalSet>()
+                .ranges()
+        );
+
+        let inverse = map.inverse().unwrap();
+        assert_eq!(inverse.apply(50), 98);
+        assert_eq!(map.then(&inverse), PiecewiseMap::identity());
+        assert_eq!(inverse.then(&map), PiecewiseMap::identity());
+
+        let shift = PiecewiseMap::from_iter([(0..10, 5)]);
+        let composed = map.then(&shift);
+        for value in [0, 3, 49, 50, 97, 98, 99, 100] {
+            assert_eq!(composed.apply(value), shift.apply(map.apply(value)));
+        }
+        // 5..10 and the untouched 10..15 both end up in 10..15
+        assert_eq!(shift.inverse(), None);
+    }
+}
@@ -2,6 +2,126 @@
 macro_rules! regex {
     ($re:literal $(,)?) => {{
         static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
-        RE.get_or_init(|| regex::Regex::new($re).unwrap())
+        #[allow(clippy::regex_creation_in_loops)]
+        let re = RE.get_or_init(|| regex::Regex::new($re).unwrap());
+        re
     }};
 }
+
+/// Build a struct or a tuple from the named groups of `regex::Captures`, parsing each of them with
+/// `FromStr`
+///
+/// `parse_caps!(caps, Part { x, m: "group" })` reads the field `x` from the group of the same name,
+/// `parse_caps!(caps, ("first", "second"))` builds a tuple. The types are inferred from the struct
+/// or from the context, and the result is a `Result<_, CaptureError>`.
+#[macro_export]
+macro_rules! parse_caps {
+    ($caps:expr, $type:ident { $($field:ident $(: $group:literal)?),+ $(,)? }) => {{
+        let caps = &$caps;
+        'parse: {
+            Ok($type {
+                $($field: match $crate::macros::parse_capture(
+                    caps,
+                    $crate::parse_caps!(@group $field $($group)?),
+                ) {
+                    Ok(value) => value,
+                    Err(error) => break 'parse Err(error),
+                },)+
+            })
+        }
+    }};
+    ($caps:expr, ($($group:literal),+ $(,)?)) => {{
+        let caps = &$caps;
+        'parse: {
+            Ok(($(match $crate::macros::parse_capture(caps, $group) {
+                Ok(value) => value,
+                Err(error) => break 'parse Err(error),
+            },)+))
+        }
+    }};
+    (@group $field:ident) => {
+        stringify!($field)
+    };
+    (@group $field:ident $group:literal) => {
+        $group
+    };
+}
+
+/// A named group that didn't match, or whose text couldn't be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureError {
+    pub group: &'static str,
+    pub text: Option<String>,
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.text {
+            Some(text) => write!(f, "cannot parse group `{}` from {text:?}", self.group),
+            None => write!(f, "group `{}` did not match", self.group),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+pub fn parse_capture<T: std::str::FromStr>(
+    caps: &regex::Captures,
+    group: &'static str,
+) -> Result<T, CaptureError> {
+    let text = caps.name(group).ok_or(CaptureError { group, text: None })?;
+    text.as_str().parse().map_err(|_| CaptureError {
+        group,
+        text: Some(text.as_str().to_owned()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[derive(Debug, PartialEq)]
+    struct Cube {
+        count: u32,
+        colour: String,
+    }
+
+    #[test]
+    fn test_parse_caps() {
+        let re = regex!(r"(?P<count>\w+) (?P<name>\w+)(?: (?P<unmatched>x))?");
+        let caps = re.captures("3 blue").unwrap();
+        assert_eq!(
+            parse_caps!(
+                caps,
+                Cube {
+                    count,
+                    colour: "name"
+                }
+            ),
+            Ok(Cube {
+                count: 3,
+                colour: "blue".to_owned()
+            })
+        );
+        assert_eq!(
+            parse_caps!(caps, ("name", "count")),
+            Ok(("blue".to_owned(), 3u8))
+        );
+
+        let caps = re.captures("three blue").unwrap();
+        let error = parse_caps!(
+            caps,
+            Cube {
+                count,
+                colour: "name"
+            }
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"cannot parse group `count` from "three""#
+        );
+        let error = parse_caps!(caps, ("name", "unmatched"))
+            .map(|(_, _): (String, String)| ())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "group `unmatched` did not match");
+    }
+}
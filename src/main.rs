@@ -1,7 +1,12 @@
+use std::env;
 use std::fs::read_to_string;
+use std::process;
 use std::time::Duration;
 
+use cache::CacheStats;
+
 mod cache;
+mod cycle;
 mod day01;
 mod day02;
 mod day03;
@@ -14,6 +19,7 @@ mod day09;
 mod day10;
 mod day11;
 mod day12;
+mod day12slow;
 mod day13;
 mod day14;
 mod day15;
@@ -23,133 +29,195 @@ mod day18;
 mod day19;
 mod day20;
 mod grid;
+mod grid3;
+mod input;
+mod interner;
+mod intervals;
 mod macros;
+mod parse;
+mod scanner;
+
+type Timed = (String, Duration, Vec<CacheStats>);
 
-fn timed<F, O>(function: F, input: &str) -> (String, Duration)
+/// Run a part, and collect the statistics of the day's memoized functions if `cache_stats` is given
+fn timed<F, O>(function: F, input: &str, cache_stats: Option<fn() -> Vec<CacheStats>>) -> Timed
 where
     F: FnOnce(&str) -> O,
     O: std::fmt::Display,
 {
+    let before = cache_stats.map(|cache_stats| cache_stats());
     let start = std::time::Instant::now();
     let output = function(input);
     let elapsed = start.elapsed();
-    (format!("{output}"), elapsed)
+    let stats = match (cache_stats, before) {
+        (Some(cache_stats), Some(before)) => cache_stats()
+            .into_iter()
+            .zip(before)
+            .map(|(after, before)| after.since(&before))
+            .collect(),
+        _ => Vec::new(),
+    };
+    (format!("{output}"), elapsed, stats)
+}
+
+/// Read the input of a day, normalized, and report on stderr what normalizing it changed
+fn read_input(day: usize, trim: bool) -> String {
+    let path = format!("inputs/{day:02}");
+    let (input, changes) = input::normalize(&read_to_string(&path).unwrap(), trim);
+    for change in changes {
+        eprintln!("{path}: {change}");
+    }
+    input
 }
 
 fn main() {
+    let show_cache_stats = env::args().any(|arg| arg == "--cache-stats");
+    let trim = env::args().any(|arg| arg == "--trim");
+    // the naive day 12, resumable when AOC_CACHE_DIR is set
+    let day12slow = env::args().any(|arg| arg == "--day12slow");
+    let cache_stats =
+        |day_cache_stats: fn() -> Vec<CacheStats>| show_cache_stats.then_some(day_cache_stats);
     let mut results = Vec::with_capacity(25);
-    let input = read_to_string("inputs/01").unwrap();
-    results.push((
-        timed(day01::part_one, &input),
-        timed(day01::part_two, &input),
-    ));
-    let input = read_to_string("inputs/02").unwrap();
+    let input = read_input(1, trim);
+    if let Some(format) = env::args().find_map(|arg| {
+        arg.strip_prefix("--calibration-report=")
+            .map(|format| format.to_owned())
+    }) {
+        let Ok(format) = format.parse::<day01::ReportFormat>() else {
+            eprintln!("--calibration-report: expected table or json, got {format:?}");
+            process::exit(2);
+        };
+        // the decoding of part two, unless --calibration-part=1
+        let part = env::args()
+            .find_map(|arg| {
+                arg.strip_prefix("--calibration-part=")
+                    .map(|part| part.to_owned())
+            })
+            .unwrap_or("2".to_owned());
+        let Some(vocabulary) = part.parse().ok().and_then(day01::vocabulary) else {
+            eprintln!("--calibration-part: expected 1 or 2, got {part:?}");
+            process::exit(2);
+        };
+        print!("{}", day01::report(&input, vocabulary, format));
+    }
     results.push((
-        timed(day02::part_one, &input),
-        timed(day02::part_two, &input),
+        timed(day01::part_one, &input, None),
+        timed(day01::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/03").unwrap();
+    let input = read_input(2, trim);
     results.push((
-        timed(day03::part_one, &input),
-        timed(day03::part_two, &input),
+        timed(day02::part_one, &input, None),
+        timed(day02::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/04").unwrap();
+    let input = read_input(3, trim);
     results.push((
-        timed(day04::part_one, &input),
-        timed(day04::part_two, &input),
+        timed(day03::part_one, &input, None),
+        timed(day03::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/05").unwrap();
+    let input = read_input(4, trim);
     results.push((
-        timed(day05::part_one, &input),
-        timed(day05::part_two, &input),
+        timed(day04::part_one, &input, None),
+        timed(day04::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/06").unwrap();
+    let input = read_input(5, trim);
     results.push((
-        timed(day06::part_one, &input),
-        timed(day06::part_two, &input),
+        timed(day05::part_one, &input, None),
+        timed(day05::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/07").unwrap();
+    let input = read_input(6, trim);
     results.push((
-        timed(day07::part_one, &input),
-        timed(day07::part_two, &input),
+        timed(day06::part_one, &input, None),
+        timed(day06::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/08").unwrap();
+    let input = read_input(7, trim);
     results.push((
-        timed(day08::part_one, &input),
-        timed(day08::part_two, &input),
+        timed(day07::part_one, &input, None),
+        timed(day07::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/09").unwrap();
+    let input = read_input(8, trim);
     results.push((
-        timed(day09::part_one, &input),
-        timed(day09::part_two, &input),
+        timed(day08::part_one, &input, None),
+        timed(day08::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/10").unwrap();
+    let input = read_input(9, trim);
     results.push((
-        timed(day10::part_one, &input),
-        timed(day10::part_two, &input),
+        timed(day09::part_one, &input, None),
+        timed(day09::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/11").unwrap();
+    let input = read_input(10, trim);
     results.push((
-        timed(day11::part_one, &input),
-        timed(day11::part_two, &input),
+        timed(day10::part_one, &input, None),
+        timed(day10::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/12").unwrap();
+    let input = read_input(11, trim);
     results.push((
-        timed(day12::part_one, &input),
-        timed(day12::part_two, &input),
+        timed(day11::part_one, &input, None),
+        timed(day11::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/13").unwrap();
+    let input = read_input(12, trim);
+    results.push(if day12slow {
+        (
+            timed(day12slow::part_one, &input, None),
+            timed(day12slow::part_two, &input, None),
+        )
+    } else {
+        (
+            timed(day12::part_one, &input, cache_stats(day12::cache_stats)),
+            timed(day12::part_two, &input, cache_stats(day12::cache_stats)),
+        )
+    });
+    let input = read_input(13, trim);
     results.push((
-        timed(day13::part_one, &input),
-        timed(day13::part_two, &input),
+        timed(day13::part_one, &input, None),
+        timed(day13::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/14").unwrap();
+    let input = read_input(14, trim);
     results.push((
-        timed(day14::part_one, &input),
-        timed(day14::part_two, &input),
+        timed(day14::part_one, &input, None),
+        timed(day14::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/15").unwrap();
+    let input = read_input(15, trim);
     results.push((
-        timed(day15::part_one, &input),
-        timed(day15::part_two, &input),
+        timed(day15::part_one, &input, None),
+        timed(day15::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/16").unwrap();
+    let input = read_input(16, trim);
     results.push((
-        timed(day16::part_one, &input),
-        timed(day16::part_two, &input),
+        timed(day16::part_one, &input, None),
+        timed(day16::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/17").unwrap();
+    let input = read_input(17, trim);
     results.push((
-        timed(day17::part_one, &input),
-        timed(day17::part_two, &input),
+        timed(day17::part_one, &input, None),
+        timed(day17::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/18").unwrap();
+    let input = read_input(18, trim);
     results.push((
-        timed(day18::part_one, &input),
-        timed(day18::part_two, &input),
+        timed(day18::part_one, &input, None),
+        timed(day18::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/19").unwrap();
+    let input = read_input(19, trim);
     results.push((
-        timed(day19::part_one, &input),
-        timed(day19::part_two, &input),
+        timed(day19::part_one, &input, None),
+        timed(day19::part_two, &input, None),
     ));
-    let input = read_to_string("inputs/20").unwrap();
+    let input = read_input(20, trim);
     results.push((
-        timed(day20::part_one, &input),
-        timed(day20::part_two, &input),
+        timed(day20::part_one, &input, None),
+        timed(day20::part_two, &input, None),
     ));
     let mut total_duration = Duration::ZERO;
-    for (day, ((part_one_answer, part_one_duration), (part_two_answer, part_two_duration))) in
-        results.into_iter().enumerate()
-    {
+    for (day, (part_one, part_two)) in results.into_iter().enumerate() {
         println!("# Day {}", day + 1);
-        println!("## Part 1");
-        println!("{part_one_answer}");
-        println!("computed in {part_one_duration:?}");
-        println!("## Part 2");
-        println!("{part_two_answer}");
-        println!("computed in {part_two_duration:?}");
-        total_duration += part_one_duration + part_two_duration;
+        for (part, (answer, duration, stats)) in [part_one, part_two].into_iter().enumerate() {
+            println!("## Part {}", part + 1);
+            println!("{answer}");
+            println!("computed in {duration:?}");
+            for stats in stats {
+                println!("cache {stats}");
+            }
+            total_duration += duration;
+        }
     }
     println!("# Total");
     println!("computed in {:?}", total_duration);
@@ -0,0 +1,136 @@
+use std::fmt;
+
+use regex::{Captures, Regex};
+use regex_automata::hybrid::dfa::DFA;
+use regex_automata::{Anchored, Input, MatchKind};
+
+/// Where the input stopped following the grammar of a regex
+#[derive(Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+    /// The offending line, without its line break
+    pub excerpt: String,
+    /// What the regex describes, e.g. "workflows then parts"
+    pub expected: String,
+}
+
+impl ParseError {
+    fn at(input: &str, offset: usize, expected: &str) -> Self {
+        let line_start = input[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = input[offset..]
+            .find('\n')
+            .map_or(input.len(), |index| offset + index);
+        Self {
+            line: input[..offset].matches('\n').count() + 1,
+            column: input[line_start..offset].chars().count() + 1,
+            excerpt: input[line_start..line_end]
+                .trim_end_matches('\r')
+                .to_owned(),
+            expected: expected.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gutter = self.line.to_string().len();
+        writeln!(
+            f,
+            "line {}, column {}: expected {}",
+            self.line, self.column, self.expected
+        )?;
+        writeln!(f, "{} | {}", self.line, self.excerpt)?;
+        write!(f, "{:gutter$} | {:>column$}", "", "^", column = self.column)
+    }
+}
+
+// so that `unwrap` shows the excerpt
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Offset of the first byte after which no text starting like `input` can match `re` from its
+/// start, or the end of `input` if it is a valid but incomplete beginning
+fn divergence(re: &Regex, input: &str) -> usize {
+    let dfa = DFA::builder()
+        .configure(DFA::config().match_kind(MatchKind::All))
+        .build(re.as_str())
+        .unwrap();
+    let mut cache = dfa.create_cache();
+    let mut state = dfa
+        .start_state_forward(&mut cache, &Input::new(input).anchored(Anchored::Yes))
+        .unwrap();
+    for (offset, byte) in input.bytes().enumerate() {
+        state = dfa.next_state(&mut cache, state, byte).unwrap();
+        if state.is_dead() {
+            // back to the start of the character
+            return (0..=offset)
+                .rev()
+                .find(|offset| input.is_char_boundary(*offset))
+                .unwrap();
+        }
+    }
+    input.len()
+}
+
+/// Like `re.captures(input)`, but the match must cover the whole input apart from trailing
+/// whitespace, and the error tells where the input diverged from `re`
+pub fn captures<'h>(
+    re: &Regex,
+    input: &'h str,
+    expected: &str,
+) -> Result<Captures<'h>, ParseError> {
+    match re.captures(input) {
+        Some(caps) => {
+            let whole = caps.get(0).unwrap();
+            if whole.start() == 0 && input[whole.end()..].trim().is_empty() {
+                Ok(caps)
+            } else {
+                Err(ParseError::at(input, divergence(re, input), expected))
+            }
+        }
+        None => Err(ParseError::at(input, divergence(re, input), expected)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regex;
+
+    #[test]
+    fn test_parse_errors() {
+        let re = regex!(r"(?P<instructions>[LR]+)\n\n(?:[A-Z]{3} = \([A-Z]{3}, [A-Z]{3}\)\n)+");
+        assert!(captures(re, "LR\n\nAAA = (BBB, CCC)\n\n", "nodes").is_ok());
+
+        let error = captures(re, "LR\n\nAAA = (BBB, CCC)\nBBB = (CCC DDD)\n", "nodes").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 11));
+        assert_eq!(
+            error.to_string(),
+            "line 4, column 11: expected nodes\n4 | BBB = (CCC DDD)\n  |           ^"
+        );
+
+        // a valid beginning that is cut short
+        let error = captures(re, "LRL\n\nAAA = (", "nodes").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.excerpt.as_str()),
+            (3, 8, "AAA = (")
+        );
+
+        let error = captures(re, "LéR\n", "nodes").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 2));
+
+        // leading or trailing text that a plain search would skip
+        let error = captures(re, "#LR\n\nAAA = (BBB, CCC)\n", "nodes").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 1));
+        let error = captures(re, "LR\n\nAAA = (BBB, CCC)\nAAA\n", "nodes").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 4));
+    }
+}
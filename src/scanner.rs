@@ -0,0 +1,182 @@
+use std::iter;
+
+use num::{PrimInt, Signed};
+
+/// Cursor over the bytes of an input, for formats too simple to need a regex
+///
+/// Nothing is allocated: numbers are read digit by digit, and text is returned as slices of the
+/// input.
+#[derive(Debug, Clone)]
+pub struct Scanner<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+/// The lines of `input`, each with its own scanner
+pub fn lines(input: &str) -> impl Iterator<Item = Scanner<'_>> {
+    input.lines().map(Scanner::new)
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position == self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.position).copied()
+    }
+
+    /// What is left to scan
+    pub fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    pub fn skip_spaces(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.position += 1;
+        }
+    }
+
+    /// Consume `token` if the input continues with it
+    pub fn token(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume `token`, which must come next
+    pub fn expect(&mut self, token: &str) {
+        assert!(
+            self.token(token),
+            "expected {token:?} at column {} of {:?}",
+            self.position + 1,
+            self.input
+        );
+    }
+
+    /// Consume the longest run of bytes that satisfy `predicate`
+    pub fn take_while(&mut self, predicate: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.position;
+        while self.peek().is_some_and(&predicate) {
+            self.position += 1;
+        }
+        &self.input[start..self.position]
+    }
+
+    /// Consume everything up to the first of `separators`, which is left in place
+    pub fn take_until(&mut self, separators: &[u8]) -> &'a str {
+        self.take_while(|byte| !separators.contains(&byte))
+    }
+
+    /// Consume digits into a number, or nothing if there are none or the number doesn't fit in `T`
+    ///
+    /// Negative numbers are accumulated downwards, so that the minimum of `T` fits.
+    fn number<T: PrimInt>(&mut self, negative: bool) -> Option<T> {
+        let start = self.position;
+        let ten = T::from(10).unwrap();
+        let digits = self.take_while(|byte| byte.is_ascii_digit());
+        let value = digits.bytes().try_fold(T::zero(), |value, digit| {
+            let digit = T::from(digit - b'0').unwrap();
+            let value = value.checked_mul(&ten)?;
+            if negative {
+                value.checked_sub(&digit)
+            } else {
+                value.checked_add(&digit)
+            }
+        });
+        if digits.is_empty() || value.is_none() {
+            self.position = start;
+            return None;
+        }
+        value
+    }
+
+    pub fn unsigned<T: PrimInt>(&mut self) -> Option<T> {
+        self.number(false)
+    }
+
+    pub fn signed<T: PrimInt + Signed>(&mut self) -> Option<T> {
+        let start = self.position;
+        let negative = self.token("-");
+        let value = self.number(negative);
+        if value.is_none() {
+            self.position = start;
+        }
+        value
+    }
+
+    /// Consume space-separated unsigned integers, until something else comes
+    pub fn unsigneds<T: PrimInt>(&mut self) -> impl Iterator<Item = T> + use<'_, 'a, T> {
+        iter::from_fn(move || {
+            self.skip_spaces();
+            self.unsigned()
+        })
+    }
+
+    /// Consume space-separated signed integers, until something else comes
+    pub fn signeds<T: PrimInt + Signed>(&mut self) -> impl Iterator<Item = T> + use<'_, 'a, T> {
+        iter::from_fn(move || {
+            self.skip_spaces();
+            self.signed()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scanner() {
+        let mut scanner = Scanner::new("Card  1: 41 48 | -83 86 x");
+        assert!(!scanner.token("Game"));
+        scanner.expect("Card");
+        assert_eq!(scanner.unsigned::<u32>(), None);
+        scanner.skip_spaces();
+        assert_eq!(scanner.unsigned::<u32>(), Some(1));
+        scanner.expect(":");
+        assert_eq!(scanner.unsigneds::<u8>().collect::<Vec<_>>(), [41, 48]);
+        scanner.expect("|");
+        assert_eq!(scanner.signeds::<i64>().collect::<Vec<_>>(), [-83, 86]);
+        assert_eq!(scanner.peek(), Some(b'x'));
+        assert_eq!(scanner.take_until(b",\n"), "x");
+        assert!(scanner.is_empty());
+
+        let mut scanner = Scanner::new("-a");
+        assert_eq!(scanner.signed::<i32>(), None);
+        assert_eq!(scanner.rest(), "-a");
+
+        // numbers that don't fit are left in place
+        let mut scanner = Scanner::new("300 -129");
+        assert_eq!(scanner.unsigned::<u8>(), None);
+        assert_eq!(scanner.unsigned::<u16>(), Some(300));
+        scanner.skip_spaces();
+        assert_eq!(scanner.signed::<i8>(), None);
+        assert_eq!(scanner.rest(), "-129");
+        assert_eq!(Scanner::new("-128").signed::<i8>(), Some(-128));
+        assert_eq!(Scanner::new("127").signed::<i8>(), Some(127));
+
+        assert_eq!(
+            lines("1 2\n3\n")
+                .map(|mut line| line.unsigneds::<u64>().sum::<u64>())
+                .collect::<Vec<_>>(),
+            [3, 3]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = r#"expected ":" at column 7 of "Card 1 | 2""#)]
+    fn test_expect() {
+        let mut scanner = Scanner::new("Card 1 | 2");
+        scanner.expect("Card ");
+        scanner.unsigned::<u32>();
+        scanner.expect(":");
+    }
+}